@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use chrono::{DateTime, FixedOffset, Offset, TimeDelta, Utc};
+
+use crate::{LogEntry, request_path};
+
+/// Traffic anomalies detection settings
+#[derive(Debug, Clone, Copy)]
+pub struct AnomalyConfig {
+    /// Time bucket size
+    pub bucket: TimeDelta,
+    /// The number of buckets that form baseline. Bigger window means slower adaptation
+    pub window: u32,
+    /// How many standard deviations from baseline make bucket anomalous
+    pub threshold: f64,
+    /// Minimal number of requests in bucket to report client spike or error rate deviation
+    pub min_requests: u64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            bucket: TimeDelta::hours(1),
+            window: 24,
+            threshold: 4.0,
+            min_requests: 100,
+        }
+    }
+}
+
+/// Series the anomaly was found in
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Scope {
+    All,
+    Host(String),
+    Client(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Requests,
+    ErrorRate,
+    Bytes,
+}
+
+/// Time bucket which metric deviates from the baseline
+#[derive(Debug)]
+pub struct Anomaly {
+    pub start: DateTime<FixedOffset>,
+    pub scope: Scope,
+    pub metric: Metric,
+    pub value: f64,
+    pub baseline: f64,
+    /// Deviation from baseline in standard deviations. Negative means drop
+    pub score: f64,
+    /// Clients that made most requests in the bucket
+    pub top_clients: Vec<(String, u64)>,
+    /// Paths requested most in the bucket
+    pub top_paths: Vec<(String, u64)>,
+}
+
+/// Streaming anomaly detector. Entries are split by time buckets and each closed bucket metrics
+/// are compared with exponentially weighted moving average and deviation of previous buckets
+/// for all traffic, each host and each client. Entries are expected to be in chronological order.
+#[derive(Debug)]
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    alpha: f64,
+    top: usize,
+    offset: Option<FixedOffset>,
+    current: Option<i64>,
+    /// The latest timestamp seen to find out whether the last bucket is covered by log completely
+    last: i64,
+    stats: HashMap<Scope, BucketStats>,
+    baselines: HashMap<Scope, Baseline>,
+    anomalies: Vec<Anomaly>,
+}
+
+#[derive(Debug, Default)]
+struct BucketStats {
+    requests: u64,
+    errors: u64,
+    bytes: u64,
+    clients: HashMap<String, u64>,
+    paths: HashMap<String, u64>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Ewma {
+    mean: f64,
+    variance: f64,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Baseline {
+    buckets: u32,
+    requests: Ewma,
+    bytes: Ewma,
+    error_rate: Ewma,
+}
+
+impl AnomalyDetector {
+    /// Creates detector that reports `top` contributing clients and paths for each anomaly
+    #[must_use]
+    pub fn new(config: AnomalyConfig, top: usize) -> Self {
+        Self {
+            alpha: 2.0 / (f64::from(config.window.max(1)) + 1.0),
+            config,
+            top,
+            offset: None,
+            current: None,
+            last: i64::MIN,
+            stats: HashMap::new(),
+            baselines: HashMap::new(),
+            anomalies: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, entry: &LogEntry) {
+        if !entry.has_timestamp() {
+            return;
+        }
+        let bucket = self.config.bucket.num_seconds().max(1);
+        let offset = i64::from(
+            self.offset
+                .get_or_insert(*entry.timestamp.offset())
+                .local_minus_utc(),
+        );
+        let start = (entry.timestamp.timestamp() + offset).div_euclid(bucket) * bucket - offset;
+        self.last = self.last.max(entry.timestamp.timestamp());
+
+        match self.current {
+            None => self.current = Some(start),
+            Some(current) if start > current => {
+                self.close_bucket(current, true);
+                // empty buckets between are part of the baseline too. Long gaps are not
+                // iterated completely because baseline decays to zero quickly
+                let gap = (start - current) / bucket - 1;
+                let gap = gap.min(i64::from(self.config.window) * 4);
+                for i in 0..gap {
+                    self.close_bucket(start - (gap - i) * bucket, true);
+                }
+                self.current = Some(start);
+            }
+            // out of order entries are counted in the current bucket
+            Some(_) => {}
+        }
+
+        let is_error = entry.status >= 400;
+        let path = request_path(&entry.request);
+        for scope in [
+            Scope::All,
+            Scope::Host(entry.host.clone()),
+            Scope::Client(entry.clientip.clone()),
+        ] {
+            let is_client = matches!(scope, Scope::Client(_));
+            let stats = self.stats.entry(scope).or_default();
+            stats.requests += 1;
+            stats.bytes += entry.length;
+            if is_error {
+                stats.errors += 1;
+            }
+            if !is_client {
+                increment(&mut stats.clients, &entry.clientip);
+            }
+            increment(&mut stats.paths, path);
+        }
+    }
+
+    /// Closes the last bucket and returns all anomalies found in chronological order.
+    /// Drops aren't reported for the last bucket if log ends before the bucket end
+    #[must_use]
+    pub fn finish(mut self) -> Vec<Anomaly> {
+        if let Some(current) = self.current {
+            let complete = self.last + 1 >= current + self.config.bucket.num_seconds().max(1);
+            self.close_bucket(current, complete);
+        }
+        self.anomalies
+    }
+
+    /// Compares bucket metrics with baselines and updates them. Drops are reported for `complete` buckets only
+    #[allow(clippy::cast_precision_loss)]
+    fn close_bucket(&mut self, start: i64, complete: bool) {
+        let mut stats = std::mem::take(&mut self.stats);
+        let warmup = self.config.window.div_ceil(2);
+        let time = DateTime::from_timestamp(start, 0)
+            .unwrap_or_default()
+            .with_timezone(&self.offset.unwrap_or(Utc.fix()));
+
+        let mut scopes: Vec<Scope> = self.baselines.keys().cloned().collect();
+        scopes.extend(
+            stats
+                .keys()
+                .filter(|s| !self.baselines.contains_key(*s))
+                .cloned(),
+        );
+        scopes.sort_unstable();
+        let warmed_up = self
+            .baselines
+            .get(&Scope::All)
+            .is_some_and(|b| b.buckets >= warmup);
+
+        for scope in scopes {
+            let current = stats.remove(&scope).unwrap_or_default();
+            let is_client = matches!(scope, Scope::Client(_));
+            let baseline = self.baselines.entry(scope.clone()).or_insert_with(|| {
+                // unknown clients had no traffic before so new client with lots of requests is anomaly
+                Baseline {
+                    buckets: if is_client && warmed_up { warmup } else { 0 },
+                    ..Default::default()
+                }
+            });
+
+            let requests = current.requests as f64;
+            let bytes = current.bytes as f64;
+            let mut found = vec![];
+            if baseline.buckets >= warmup {
+                let min_requests = if is_client {
+                    self.config.min_requests
+                } else {
+                    1
+                };
+                // spikes are reported for all scopes but drops for all traffic and hosts only
+                let reported = |score: f64| {
+                    score.abs() > self.config.threshold
+                        && (score > 0.0 && current.requests >= min_requests
+                            || score < 0.0 && !is_client && complete)
+                };
+                let score = baseline.requests.score(requests, requests_floor);
+                if reported(score) {
+                    found.push((Metric::Requests, requests, baseline.requests.mean, score));
+                }
+                let score = baseline.bytes.score(bytes, bytes_floor);
+                if reported(score) {
+                    found.push((Metric::Bytes, bytes, baseline.bytes.mean, score));
+                }
+            }
+            let error_rate = if current.requests > 0 {
+                Some(current.errors as f64 / current.requests as f64)
+            } else {
+                None
+            };
+            if let Some(rate) = error_rate
+                && baseline.buckets >= warmup
+                && current.requests >= self.config.min_requests
+            {
+                let score = baseline.error_rate.score(rate, |_| 0.05);
+                if score > self.config.threshold {
+                    found.push((Metric::ErrorRate, rate, baseline.error_rate.mean, score));
+                }
+            }
+
+            baseline.buckets = baseline.buckets.saturating_add(1);
+            baseline.requests.update(requests, self.alpha);
+            baseline.bytes.update(bytes, self.alpha);
+            if let Some(rate) = error_rate {
+                baseline.error_rate.update(rate, self.alpha);
+            }
+            // forget clients that have gone away
+            if is_client && baseline.requests.mean < 0.01 {
+                self.baselines.remove(&scope);
+            }
+
+            for (metric, value, baseline, score) in found {
+                self.anomalies.push(Anomaly {
+                    start: time,
+                    scope: scope.clone(),
+                    metric,
+                    value,
+                    baseline,
+                    score,
+                    top_clients: top(&current.clients, self.top),
+                    top_paths: top(&current.paths, self.top),
+                });
+            }
+        }
+    }
+}
+
+impl Ewma {
+    fn update(&mut self, value: f64, alpha: f64) {
+        let diff = value - self.mean;
+        let increment = alpha * diff;
+        self.mean += increment;
+        self.variance = (1.0 - alpha) * (self.variance + diff * increment);
+    }
+
+    /// Deviation of the value in standard deviations. Standard deviation
+    /// is never less than `floor(mean)` so that almost constant series do not produce false alarms
+    fn score(&self, value: f64, floor: impl Fn(f64) -> f64) -> f64 {
+        let deviation = self.variance.sqrt().max(floor(self.mean));
+        (value - self.mean) / deviation
+    }
+}
+
+/// Requests count is supposed to have Poisson-like noise at least
+fn requests_floor(mean: f64) -> f64 {
+    mean.sqrt().max(1.0)
+}
+
+fn bytes_floor(mean: f64) -> f64 {
+    (mean * 0.1).max(1024.0)
+}
+
+fn increment(counts: &mut HashMap<String, u64>, key: &str) {
+    if let Some(c) = counts.get_mut(key) {
+        *c += 1;
+    } else {
+        counts.insert(key.to_owned(), 1);
+    }
+}
+
+fn top(counts: &HashMap<String, u64>, limit: usize) -> Vec<(String, u64)> {
+    let mut result: Vec<_> = counts.iter().map(|(k, v)| (k.clone(), *v)).collect();
+    result.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    result.truncate(limit);
+    result
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scope::All => write!(f, "all"),
+            Scope::Host(host) => write!(f, "host {host}"),
+            Scope::Client(client) => write!(f, "client {client}"),
+        }
+    }
+}
+
+impl Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Metric::Requests => write!(f, "Requests"),
+            Metric::ErrorRate => write!(f, "Error rate"),
+            Metric::Bytes => write!(f, "Bytes"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(client: &str, hours: i64, status: u16) -> LogEntry {
+        LogEntryBuilder::new()
+            .client(client)
+            .host("example.com")
+            .request("/index.html")
+            .status(status)
+            .length(1000)
+            .after(TimeDelta::hours(hours))
+            .build()
+    }
+
+    fn hour(h: i64) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2024-05-01T00:00:00Z").unwrap() + TimeDelta::hours(h)
+    }
+
+    fn steady_traffic(detector: &mut AnomalyDetector, hours: i64) {
+        for h in 0..hours {
+            for i in 0..50 {
+                detector.add(&entry(&format!("10.0.0.{i}"), h, 200));
+            }
+        }
+    }
+
+    #[test]
+    fn steady_traffic_has_no_anomalies() {
+        // Arrange
+        let mut detector = AnomalyDetector::new(AnomalyConfig::default(), 3);
+
+        // Act
+        steady_traffic(&mut detector, 48);
+        let anomalies = detector.finish();
+
+        // Assert
+        assert!(anomalies.is_empty(), "{anomalies:?}");
+    }
+
+    #[test]
+    fn log_ended_within_bucket_has_no_drop() {
+        // Arrange
+        let config = AnomalyConfig {
+            bucket: TimeDelta::minutes(5),
+            ..Default::default()
+        };
+        let mut detector = AnomalyDetector::new(config, 3);
+
+        // Act
+        // 10 requests per minute for 6 hours and 1 minute
+        for minute in 0..=360 {
+            for i in 0..10 {
+                let e = LogEntryBuilder::new()
+                    .client(&format!("10.0.0.{i}"))
+                    .host("example.com")
+                    .after(TimeDelta::minutes(minute))
+                    .build();
+                detector.add(&e);
+            }
+        }
+        let anomalies = detector.finish();
+
+        // Assert
+        assert!(anomalies.is_empty(), "{anomalies:?}");
+    }
+
+    #[test]
+    fn client_spike_detected() {
+        // Arrange
+        let mut detector = AnomalyDetector::new(AnomalyConfig::default(), 3);
+        steady_traffic(&mut detector, 48);
+
+        // Act
+        for _ in 0..1000 {
+            detector.add(&entry("203.0.113.7", 48, 200));
+        }
+        let anomalies = detector.finish();
+
+        // Assert
+        let client = anomalies
+            .iter()
+            .find(|a| a.scope == Scope::Client("203.0.113.7".to_owned()))
+            .unwrap();
+        assert_eq!(client.metric, Metric::Requests);
+        assert_eq!(client.start, hour(48));
+        let all = anomalies
+            .iter()
+            .find(|a| a.scope == Scope::All && a.metric == Metric::Requests)
+            .unwrap();
+        assert_eq!(all.top_clients[0], ("203.0.113.7".to_owned(), 1000));
+    }
+
+    #[test]
+    fn error_rate_detected() {
+        // Arrange
+        let mut detector = AnomalyDetector::new(AnomalyConfig::default(), 3);
+        steady_traffic(&mut detector, 48);
+
+        // Act
+        for i in 0..200 {
+            detector.add(&entry(&format!("10.0.0.{}", i % 50), 48, 502));
+        }
+        let anomalies = detector.finish();
+
+        // Assert
+        assert!(
+            anomalies
+                .iter()
+                .any(|a| a.scope == Scope::All && a.metric == Metric::ErrorRate)
+        );
+    }
+
+    #[test]
+    fn traffic_drop_detected() {
+        // Arrange
+        let mut detector = AnomalyDetector::new(AnomalyConfig::default(), 3);
+        steady_traffic(&mut detector, 48);
+
+        // Act
+        detector.add(&entry("10.0.0.1", 49, 200));
+        let anomalies = detector.finish();
+
+        // Assert
+        let drop = anomalies
+            .iter()
+            .find(|a| a.scope == Scope::All && a.metric == Metric::Requests)
+            .unwrap();
+        assert!(drop.score < 0.0);
+        assert_eq!(drop.start, hour(48));
+    }
+}
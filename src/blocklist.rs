@@ -0,0 +1,328 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use clap::{ValueEnum, builder::PossibleValue};
+use color_eyre::eyre::{Result, eyre};
+
+/// Prefix length IPv4 addresses are aggregated to
+const IPV4_AGGREGATE_PREFIX: u8 = 24;
+/// Prefix length IPv6 addresses are aggregated to
+const IPV6_AGGREGATE_PREFIX: u8 = 64;
+
+/// Block list file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlocklistFormat {
+    /// `deny` directives to include into nginx configuration
+    NginxDeny,
+    /// `ipset restore` input
+    Ipset,
+    /// `fail2ban-client` commands banning addresses in jail
+    Fail2ban,
+    /// One address or network per line
+    Plain,
+}
+
+/// IP address or network in CIDR notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Network {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+/// Collects client addresses excluding allowed ones and renders them as block list
+#[derive(Debug, Default)]
+pub struct Blocklist {
+    aggregate: bool,
+    allowlist: Vec<Network>,
+    networks: BTreeSet<Network>,
+}
+
+impl Network {
+    fn new(addr: IpAddr, prefix: u8) -> Self {
+        let addr = match addr {
+            IpAddr::V4(a) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(a) & mask))
+            }
+            IpAddr::V6(a) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(a) & mask))
+            }
+        };
+        Self { addr, prefix }
+    }
+
+    fn host(addr: IpAddr) -> Self {
+        Self::new(addr, max_prefix(addr))
+    }
+
+    #[must_use]
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        addr.is_ipv4() == self.addr.is_ipv4() && Self::new(addr, self.prefix).addr == self.addr
+    }
+
+    /// Whether networks have common addresses
+    fn overlaps(&self, other: &Network) -> bool {
+        if self.prefix <= other.prefix {
+            self.contains(other.addr)
+        } else {
+            other.contains(self.addr)
+        }
+    }
+
+    fn is_host(&self) -> bool {
+        self.prefix == max_prefix(self.addr)
+    }
+}
+
+fn max_prefix(addr: IpAddr) -> u8 {
+    if addr.is_ipv4() { 32 } else { 128 }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid IP address or network '{value}'");
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value, None),
+        };
+        let addr: IpAddr = addr.trim().parse().map_err(|_| invalid())?;
+        let prefix = match prefix {
+            Some(p) => p.trim().parse::<u8>().map_err(|_| invalid())?,
+            None => max_prefix(addr),
+        };
+        if prefix > max_prefix(addr) {
+            return Err(invalid());
+        }
+        Ok(Self::new(addr, prefix))
+    }
+}
+
+impl Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_host() {
+            write!(f, "{}", self.addr)
+        } else {
+            write!(f, "{}/{}", self.addr, self.prefix)
+        }
+    }
+}
+
+/// Parses allow list of addresses and networks one per line.
+/// Empty lines and lines started from `#` are ignored
+///
+/// # Errors
+///
+/// This function will return an error if any line isn't valid IP address or network
+pub fn parse_allowlist(text: &str) -> Result<Vec<Network>> {
+    text.lines()
+        .enumerate()
+        .map(|(number, line)| (number, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            line.parse()
+                .map_err(|e| eyre!("{e} at line {}", number + 1))
+        })
+        .collect()
+}
+
+impl Blocklist {
+    /// Creates block list. If `aggregate` is set addresses are aggregated to /24 (IPv4) and /64 (IPv6) networks
+    /// unless network contains allowed address
+    #[must_use]
+    pub fn new(aggregate: bool, allowlist: Vec<Network>) -> Self {
+        Self {
+            aggregate,
+            allowlist,
+            networks: BTreeSet::new(),
+        }
+    }
+
+    /// Adds client address. Values that aren't IP addresses and allowed addresses are ignored
+    pub fn add(&mut self, client: &str) {
+        let Ok(addr) = client.trim().parse::<IpAddr>() else {
+            return;
+        };
+        if self.allowlist.iter().any(|n| n.contains(addr)) {
+            return;
+        }
+        let host = Network::host(addr);
+        let network = if self.aggregate {
+            let prefix = if addr.is_ipv4() {
+                IPV4_AGGREGATE_PREFIX
+            } else {
+                IPV6_AGGREGATE_PREFIX
+            };
+            Some(Network::new(addr, prefix))
+                .filter(|n| !self.allowlist.iter().any(|a| a.overlaps(n)))
+        } else {
+            None
+        };
+        self.networks.insert(network.unwrap_or(host));
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.networks.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.networks.is_empty()
+    }
+
+    /// Renders block list in specified format. `name` is ipset set name or fail2ban jail name
+    #[must_use]
+    pub fn render(&self, format: BlocklistFormat, name: &str) -> String {
+        let mut result = String::new();
+        if format == BlocklistFormat::Ipset {
+            let _ = writeln!(result, "create {name} hash:net family inet -exist");
+            if self.networks.iter().any(|n| !n.addr.is_ipv4()) {
+                let _ = writeln!(result, "create {name}-v6 hash:net family inet6 -exist");
+            }
+        }
+        for network in &self.networks {
+            let line = match format {
+                BlocklistFormat::NginxDeny => format!("deny {network};"),
+                BlocklistFormat::Ipset if network.addr.is_ipv4() => {
+                    format!("add {name} {network} -exist")
+                }
+                BlocklistFormat::Ipset => format!("add {name}-v6 {network} -exist"),
+                BlocklistFormat::Fail2ban => format!("fail2ban-client set {name} banip {network}"),
+                BlocklistFormat::Plain => network.to_string(),
+            };
+            result.push_str(&line);
+            result.push('\n');
+        }
+        result
+    }
+}
+
+impl ValueEnum for BlocklistFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            BlocklistFormat::NginxDeny,
+            BlocklistFormat::Ipset,
+            BlocklistFormat::Fail2ban,
+            BlocklistFormat::Plain,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            BlocklistFormat::NginxDeny => PossibleValue::new("nginx-deny"),
+            BlocklistFormat::Ipset => PossibleValue::new("ipset"),
+            BlocklistFormat::Fail2ban => PossibleValue::new("fail2ban"),
+            BlocklistFormat::Plain => PossibleValue::new("plain"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("10.0.0.1", "10.0.0.1")]
+    #[test_case("10.0.0.1/24", "10.0.0.0/24")]
+    #[test_case("10.0.0.1/32", "10.0.0.1")]
+    #[test_case("2001:db8::1/64", "2001:db8::/64")]
+    #[test_case("10.0.0.1/0", "0.0.0.0/0")]
+    fn network_parse_tests(value: &str, expected: &str) {
+        // Arrange
+
+        // Act
+        let actual: Network = value.parse().unwrap();
+
+        // Assert
+        assert_eq!(actual.to_string(), expected);
+    }
+
+    #[test_case("")]
+    #[test_case("10.0.0")]
+    #[test_case("10.0.0.1/33")]
+    #[test_case("example.com")]
+    fn network_parse_invalid_tests(value: &str) {
+        // Arrange
+
+        // Act
+        let actual = value.parse::<Network>();
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn allowed_addresses_not_emitted() {
+        // Arrange
+        let allowlist = parse_allowlist("# office\n192.168.1.0/24\n\n10.0.0.5\n").unwrap();
+        let mut blocklist = Blocklist::new(false, allowlist);
+
+        // Act
+        for client in ["192.168.1.7", "10.0.0.5", "10.0.0.6", "-", "10.0.0.6"] {
+            blocklist.add(client);
+        }
+
+        // Assert
+        assert_eq!(blocklist.render(BlocklistFormat::Plain, ""), "10.0.0.6\n");
+    }
+
+    #[test]
+    fn addresses_aggregated() {
+        // Arrange
+        let allowlist = parse_allowlist("10.0.1.1").unwrap();
+        let mut blocklist = Blocklist::new(true, allowlist);
+
+        // Act
+        for client in [
+            "10.0.0.1",
+            "10.0.0.2",
+            "10.0.1.2",
+            "2001:db8::1",
+            "2001:db8::2",
+        ] {
+            blocklist.add(client);
+        }
+
+        // Assert
+        assert_eq!(blocklist.len(), 3);
+        assert_eq!(
+            blocklist.render(BlocklistFormat::NginxDeny, ""),
+            "deny 10.0.0.0/24;\ndeny 10.0.1.2;\ndeny 2001:db8::/64;\n"
+        );
+    }
+
+    #[test_case(
+        BlocklistFormat::Ipset,
+        "create bl hash:net family inet -exist\nadd bl 1.2.3.4 -exist\n"
+    )]
+    #[test_case(BlocklistFormat::Fail2ban, "fail2ban-client set bl banip 1.2.3.4\n")]
+    #[test_case(BlocklistFormat::Plain, "1.2.3.4\n")]
+    fn render_tests(format: BlocklistFormat, expected: &str) {
+        // Arrange
+        let mut blocklist = Blocklist::new(false, vec![]);
+        blocklist.add("1.2.3.4");
+
+        // Act
+        let actual = blocklist.render(format, "bl");
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn invalid_allowlist() {
+        // Arrange
+
+        // Act
+        let actual = parse_allowlist("10.0.0.1\nlocalhost\n");
+
+        // Assert
+        assert!(actual.is_err());
+    }
+}
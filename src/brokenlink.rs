@@ -0,0 +1,267 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use color_eyre::eyre::Result;
+
+use crate::domain::{ReferrerType, referrer_type};
+use crate::scanner::{ScannerConfig, ScannerDetector};
+use crate::{LogEntry, request_path};
+
+/// Statuses that mean broken link
+const BROKEN_STATUSES: [u16; 2] = [404, 410];
+
+/// Path answered with 404 or 410 and the pages that link to it
+#[derive(Debug)]
+pub struct BrokenLink {
+    pub path: String,
+    pub statuses: Vec<u16>,
+    pub hits: u64,
+    /// Distinct clients requested the path
+    pub clients: usize,
+    /// Hits with referrer from the same site
+    pub internal_hits: u64,
+    /// Hits with referrer from other sites
+    pub external_hits: u64,
+    /// Hits without referrer
+    pub direct_hits: u64,
+    /// Internal referrers ordered by hits
+    pub referrers: Vec<(String, u64)>,
+}
+
+/// Broken links and the number of excluded hits
+#[derive(Debug, Default)]
+pub struct BrokenLinks {
+    pub links: Vec<BrokenLink>,
+    /// 404 and 410 hits of clients detected as scanners
+    pub scanner_hits: u64,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq)]
+struct Hit {
+    path: String,
+    status: u16,
+    referrer: String,
+    client: String,
+}
+
+/// Collects broken links excluding requests of clients detected as scanners
+#[derive(Debug)]
+pub struct BrokenLinkDetector {
+    scanners: ScannerDetector,
+    min_score: f64,
+    hits: HashMap<Hit, (u64, ReferrerType)>,
+}
+
+impl BrokenLinkDetector {
+    /// Creates detector. `probes` and `min_score` are used to find scanners like `ScannerDetector` does
+    ///
+    /// # Errors
+    ///
+    /// Returns error if probes cannot be compiled
+    pub fn new(probes: &str, min_score: f64) -> Result<Self> {
+        Ok(Self {
+            scanners: ScannerDetector::new(ScannerConfig::default(), probes)?,
+            min_score,
+            hits: HashMap::new(),
+        })
+    }
+
+    pub fn add(&mut self, entry: &LogEntry) {
+        self.scanners.add(entry);
+        if !BROKEN_STATUSES.contains(&entry.status) {
+            return;
+        }
+        let hit = Hit {
+            path: request_path(&entry.request).to_owned(),
+            status: entry.status,
+            referrer: entry.referrer.clone(),
+            client: entry.clientip.clone(),
+        };
+        self.hits
+            .entry(hit)
+            .or_insert_with(|| (0, referrer_type(&entry.referrer, &entry.host)))
+            .0 += 1;
+    }
+
+    /// Returns broken links ordered by internal hits and then by all hits. Probe paths requested
+    /// by clients not scored as scanners are kept because they may be real links
+    #[must_use]
+    pub fn finish(self) -> BrokenLinks {
+        let scanners: HashSet<String> = self
+            .scanners
+            .finish(self.min_score)
+            .into_iter()
+            .map(|s| s.client)
+            .collect();
+
+        let mut scanner_hits = 0;
+        let mut links: HashMap<String, Accumulator> = HashMap::new();
+        for (hit, (count, kind)) in self.hits {
+            if scanners.contains(&hit.client) {
+                scanner_hits += count;
+                continue;
+            }
+            let link = links.entry(hit.path).or_default();
+            link.statuses.insert(hit.status);
+            link.hits += count;
+            link.clients.insert(hit.client);
+            match kind {
+                ReferrerType::Internal => {
+                    link.internal_hits += count;
+                    *link.referrers.entry(hit.referrer).or_default() += count;
+                }
+                ReferrerType::External => link.external_hits += count,
+                ReferrerType::None => link.direct_hits += count,
+            }
+        }
+
+        let mut result: Vec<_> = links
+            .into_iter()
+            .map(|(path, link)| {
+                let mut referrers: Vec<_> = link.referrers.into_iter().collect();
+                referrers.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                BrokenLink {
+                    path,
+                    statuses: link.statuses.into_iter().collect(),
+                    hits: link.hits,
+                    clients: link.clients.len(),
+                    internal_hits: link.internal_hits,
+                    external_hits: link.external_hits,
+                    direct_hits: link.direct_hits,
+                    referrers,
+                }
+            })
+            .collect();
+        result.sort_unstable_by(|a, b| {
+            b.internal_hits
+                .cmp(&a.internal_hits)
+                .then_with(|| b.hits.cmp(&a.hits))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        BrokenLinks {
+            links: result,
+            scanner_hits,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Accumulator {
+    statuses: BTreeSet<u16>,
+    hits: u64,
+    clients: HashSet<String>,
+    internal_hits: u64,
+    external_hits: u64,
+    direct_hits: u64,
+    referrers: HashMap<String, u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+    use crate::LogEntryBuilder;
+    use crate::scanner::BUNDLED_PROBES;
+
+    fn entry(client: &str, request: &str, referrer: &str, status: u16) -> LogEntryBuilder {
+        LogEntryBuilder::new()
+            .client(client)
+            .host("example.com")
+            .request(request)
+            .referrer(referrer)
+            .status(status)
+    }
+
+    #[test]
+    fn broken_links_grouped_with_referrers() {
+        // Arrange
+        let mut detector = BrokenLinkDetector::new(BUNDLED_PROBES, 10.0).unwrap();
+
+        // Act
+        detector.add(&entry("10.0.0.1", "/old-page?x=1", "https://example.com/blog", 404).build());
+        detector.add(&entry("10.0.0.2", "/old-page", "https://www.example.com/blog", 404).build());
+        detector.add(&entry("10.0.0.2", "/old-page", "https://example.com/news", 404).build());
+        detector.add(&entry("10.0.0.3", "/old-page", "https://other.org/", 404).build());
+        detector.add(&entry("10.0.0.3", "/removed", "-", 410).build());
+        detector.add(&entry("10.0.0.3", "/removed", "-", 404).build());
+        detector.add(&entry("10.0.0.1", "/blog", "-", 200).build());
+        let actual = detector.finish().links;
+
+        // Assert
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].path, "/old-page");
+        assert_eq!(actual[0].hits, 4);
+        assert_eq!(actual[0].clients, 3);
+        assert_eq!(actual[0].internal_hits, 3);
+        assert_eq!(actual[0].external_hits, 1);
+        assert_eq!(
+            actual[0].referrers,
+            vec![
+                ("https://example.com/blog".to_owned(), 1),
+                ("https://example.com/news".to_owned(), 1),
+                ("https://www.example.com/blog".to_owned(), 1),
+            ]
+        );
+        assert_eq!(actual[1].path, "/removed");
+        assert_eq!(actual[1].statuses, vec![404, 410]);
+        assert_eq!(actual[1].direct_hits, 2);
+    }
+
+    #[test]
+    fn visitor_broken_link_kept_next_to_scanner() {
+        // Arrange
+        let mut detector = BrokenLinkDetector::new(BUNDLED_PROBES, 10.0).unwrap();
+
+        // Act
+        for i in 0..5 {
+            let page = entry("10.0.0.1", "/wp-includes/js/jquery.js", "-", 200);
+            detector.add(&page.after(TimeDelta::seconds(i)).build());
+        }
+        detector.add(&entry("10.0.0.1", "/wp-admin/", "-", 302).build());
+        detector.add(
+            &entry(
+                "10.0.0.1",
+                "/2019/old-post",
+                "https://example.com/blog",
+                404,
+            )
+            .build(),
+        );
+        for i in 0..30 {
+            detector.add(
+                &entry("203.0.113.7", &format!("/guess{i}"), "-", 404)
+                    .after(TimeDelta::seconds(i))
+                    .build(),
+            );
+        }
+        let actual = detector.finish();
+
+        // Assert
+        assert_eq!(actual.scanner_hits, 30);
+        assert_eq!(actual.links.len(), 1);
+        assert_eq!(actual.links[0].path, "/2019/old-post");
+        assert_eq!(actual.links[0].internal_hits, 1);
+    }
+
+    #[test]
+    fn only_scanner_clients_excluded() {
+        // Arrange
+        let mut detector = BrokenLinkDetector::new(BUNDLED_PROBES, 10.0).unwrap();
+
+        // Act
+        detector.add(&entry("10.0.0.1", "/.env", "https://example.com/", 404).build());
+        for i in 0..30 {
+            detector.add(
+                &entry("203.0.113.7", &format!("/guess{i}"), "-", 404)
+                    .after(TimeDelta::seconds(i))
+                    .build(),
+            );
+        }
+        let actual = detector.finish();
+
+        // Assert
+        assert_eq!(actual.scanner_hits, 30);
+        assert_eq!(actual.links.len(), 1);
+        assert_eq!(actual.links[0].path, "/.env");
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, Offset, TimeDelta, Utc};
+
+use crate::{LogEntry, request_path};
+
+/// Login endpoints checked when none are configured. Endpoints answering failures with success
+/// status like `POST /wp-login.php` are not included since their failures aren't visible
+pub const DEFAULT_LOGIN_ENDPOINTS: [&str; 7] = [
+    "POST /login",
+    "POST /signin",
+    "POST /api/login",
+    "POST /api/auth/login",
+    "POST /auth/login",
+    "POST /user/login",
+    "POST /oauth/token",
+];
+
+/// Statuses that mean failed login attempt
+const FAILED_STATUSES: [u16; 3] = [401, 403, 429];
+
+/// Login endpoint like `POST /api/login`. Method is optional and path that ends with `*` is a prefix
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LoginEndpoint {
+    method: Option<String>,
+    path: String,
+}
+
+/// Brute force detection settings
+#[derive(Debug, Clone, Copy)]
+pub struct BruteForceConfig {
+    pub window: TimeDelta,
+    /// Failed attempts of single client within window to report it
+    pub client_threshold: u64,
+    /// Failed attempts to endpoint within window to report window
+    pub window_threshold: u64,
+    /// Minimal number of distinct failing clients to report window as distributed attack
+    pub min_clients: usize,
+}
+
+impl Default for BruteForceConfig {
+    fn default() -> Self {
+        Self {
+            window: TimeDelta::minutes(5),
+            client_threshold: 10,
+            window_threshold: 50,
+            min_clients: 5,
+        }
+    }
+}
+
+/// Client suspected in brute forcing
+#[derive(Debug)]
+pub struct SuspiciousClient {
+    pub client: String,
+    pub failures: u64,
+    /// Successful responses from login endpoints. Success after many failures may mean compromised account
+    pub successes: u64,
+    /// Maximum number of failures within single window
+    pub max_failures: u64,
+    pub first_seen: DateTime<FixedOffset>,
+    pub last_seen: DateTime<FixedOffset>,
+    pub endpoints: Vec<LoginEndpoint>,
+}
+
+/// Time window with lots of failed login attempts from many clients
+#[derive(Debug)]
+pub struct SuspiciousWindow {
+    pub start: DateTime<FixedOffset>,
+    pub endpoint: LoginEndpoint,
+    pub failures: u64,
+    pub clients: usize,
+    /// Clients with the most failures in the window
+    pub top_clients: Vec<(String, u64)>,
+}
+
+/// Detects single client brute force and distributed credential stuffing on login endpoints
+#[derive(Debug)]
+pub struct BruteForceDetector {
+    config: BruteForceConfig,
+    endpoints: Vec<LoginEndpoint>,
+    offset: Option<FixedOffset>,
+    clients: HashMap<String, ClientAttempts>,
+    windows: BTreeMap<(i64, LoginEndpoint), HashMap<String, u64>>,
+}
+
+#[derive(Debug)]
+struct ClientAttempts {
+    failures: u64,
+    successes: u64,
+    window: i64,
+    window_failures: u64,
+    max_failures: u64,
+    first_seen: DateTime<FixedOffset>,
+    last_seen: DateTime<FixedOffset>,
+    endpoints: Vec<LoginEndpoint>,
+}
+
+impl LoginEndpoint {
+    #[must_use]
+    pub fn matches(&self, method: &str, path: &str) -> bool {
+        self.method
+            .as_ref()
+            .is_none_or(|m| m.eq_ignore_ascii_case(method))
+            && match self.path.strip_suffix('*') {
+                Some(prefix) => path.starts_with(prefix),
+                None => path == self.path,
+            }
+    }
+}
+
+impl FromStr for LoginEndpoint {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.split_whitespace();
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(path), None, None) if path.starts_with('/') => Ok(Self {
+                method: None,
+                path: path.to_owned(),
+            }),
+            (Some(method), Some(path), None) if path.starts_with('/') => Ok(Self {
+                method: Some(method.to_uppercase()),
+                path: path.to_owned(),
+            }),
+            _ => Err(format!(
+                "Invalid login endpoint '{value}'. Use path or method and path like 'POST /api/login'"
+            )),
+        }
+    }
+}
+
+impl Display for LoginEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.method {
+            Some(method) => write!(f, "{method} {}", self.path),
+            None => write!(f, "{}", self.path),
+        }
+    }
+}
+
+impl BruteForceDetector {
+    #[must_use]
+    pub fn new(config: BruteForceConfig, endpoints: Vec<LoginEndpoint>) -> Self {
+        Self {
+            config,
+            endpoints,
+            offset: None,
+            clients: HashMap::new(),
+            windows: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, entry: &LogEntry) {
+        if !entry.has_timestamp() {
+            return;
+        }
+        let path = request_path(&entry.request);
+        let Some(endpoint) = self
+            .endpoints
+            .iter()
+            .find(|e| e.matches(&entry.method, path))
+        else {
+            return;
+        };
+        let failed = FAILED_STATUSES.contains(&entry.status);
+        let successful = (200..400).contains(&entry.status);
+        let window_size = self.config.window.num_seconds().max(1);
+        let offset = i64::from(
+            self.offset
+                .get_or_insert(*entry.timestamp.offset())
+                .local_minus_utc(),
+        );
+        let window =
+            (entry.timestamp.timestamp() + offset).div_euclid(window_size) * window_size - offset;
+
+        let client = self
+            .clients
+            .entry(entry.clientip.clone())
+            .or_insert_with(|| ClientAttempts {
+                failures: 0,
+                successes: 0,
+                window,
+                window_failures: 0,
+                max_failures: 0,
+                first_seen: entry.timestamp,
+                last_seen: entry.timestamp,
+                endpoints: vec![],
+            });
+        client.first_seen = client.first_seen.min(entry.timestamp);
+        client.last_seen = client.last_seen.max(entry.timestamp);
+        if !client.endpoints.contains(endpoint) {
+            client.endpoints.push(endpoint.clone());
+        }
+        if successful {
+            client.successes += 1;
+        }
+        if !failed {
+            return;
+        }
+        client.failures += 1;
+        if client.window != window {
+            client.window = window;
+            client.window_failures = 0;
+        }
+        client.window_failures += 1;
+        client.max_failures = client.max_failures.max(client.window_failures);
+
+        let failures = self.windows.entry((window, endpoint.clone())).or_default();
+        *failures.entry(entry.clientip.clone()).or_default() += 1;
+    }
+
+    /// Returns clients and windows that exceeded thresholds, the most active first
+    #[must_use]
+    pub fn finish(self, top: usize) -> (Vec<SuspiciousClient>, Vec<SuspiciousWindow>) {
+        let mut clients: Vec<_> = self
+            .clients
+            .into_iter()
+            .filter(|(_, c)| c.max_failures >= self.config.client_threshold)
+            .map(|(client, c)| SuspiciousClient {
+                client,
+                failures: c.failures,
+                successes: c.successes,
+                max_failures: c.max_failures,
+                first_seen: c.first_seen,
+                last_seen: c.last_seen,
+                endpoints: c.endpoints,
+            })
+            .collect();
+        clients.sort_unstable_by(|a, b| {
+            b.max_failures
+                .cmp(&a.max_failures)
+                .then_with(|| b.failures.cmp(&a.failures))
+                .then_with(|| a.client.cmp(&b.client))
+        });
+
+        let offset = self.offset.unwrap_or(Utc.fix());
+        let mut windows: Vec<_> = self
+            .windows
+            .into_iter()
+            .filter_map(|((start, endpoint), failures)| {
+                let total: u64 = failures.values().sum();
+                if total < self.config.window_threshold || failures.len() < self.config.min_clients
+                {
+                    return None;
+                }
+                let mut top_clients: Vec<_> = failures.into_iter().collect();
+                top_clients.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                let clients = top_clients.len();
+                top_clients.truncate(top);
+                Some(SuspiciousWindow {
+                    start: DateTime::from_timestamp(start, 0)?.with_timezone(&offset),
+                    endpoint,
+                    failures: total,
+                    clients,
+                    top_clients,
+                })
+            })
+            .collect();
+        windows.sort_by(|a, b| {
+            b.failures
+                .cmp(&a.failures)
+                .then_with(|| a.start.cmp(&b.start))
+        });
+        (clients, windows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(client: &str, seconds: i64, method: &str, request: &str, status: u16) -> LogEntry {
+        LogEntryBuilder::new()
+            .client(client)
+            .method(method)
+            .request(request)
+            .status(status)
+            .after(TimeDelta::seconds(seconds))
+            .build()
+    }
+
+    fn endpoints() -> Vec<LoginEndpoint> {
+        vec!["POST /api/login".parse().unwrap()]
+    }
+
+    #[test_case("POST /api/login", "post", "/api/login", true)]
+    #[test_case("POST /api/login", "GET", "/api/login", false)]
+    #[test_case("/api/login", "GET", "/api/login", true)]
+    #[test_case("/api/login", "POST", "/api/login/x", false)]
+    #[test_case("POST /auth/*", "POST", "/auth/token", true)]
+    fn endpoint_matches_tests(endpoint: &str, method: &str, path: &str, expected: bool) {
+        // Arrange
+        let endpoint: LoginEndpoint = endpoint.parse().unwrap();
+
+        // Act
+        let actual = endpoint.matches(method, path);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("POST", "/login", true)]
+    #[test_case("POST", "/api/auth/login", true)]
+    #[test_case("POST", "/wp-login.php", false ; "wordpress answers failures with 200")]
+    fn default_endpoints_tests(method: &str, path: &str, expected: bool) {
+        // Arrange
+        let endpoints: Vec<LoginEndpoint> = DEFAULT_LOGIN_ENDPOINTS
+            .iter()
+            .map(|e| e.parse().unwrap())
+            .collect();
+
+        // Act
+        let actual = endpoints.iter().any(|e| e.matches(method, path));
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("")]
+    #[test_case("POST")]
+    #[test_case("POST api/login")]
+    #[test_case("POST /a /b")]
+    fn endpoint_invalid_tests(endpoint: &str) {
+        // Arrange
+
+        // Act
+        let actual = endpoint.parse::<LoginEndpoint>();
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn single_client_brute_force_detected() {
+        // Arrange
+        let mut detector = BruteForceDetector::new(BruteForceConfig::default(), endpoints());
+
+        // Act
+        for i in 0..20 {
+            detector.add(&entry("203.0.113.7", i, "POST", "/api/login", 401));
+        }
+        detector.add(&entry("203.0.113.7", 30, "POST", "/api/login?next=/", 200));
+        detector.add(&entry("10.0.0.1", 30, "POST", "/api/login", 401));
+        detector.add(&entry("10.0.0.1", 30, "POST", "/other", 401));
+        let (clients, windows) = detector.finish(3);
+
+        // Assert
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].client, "203.0.113.7");
+        assert_eq!(clients[0].failures, 20);
+        assert_eq!(clients[0].successes, 1);
+        assert!(windows.is_empty());
+    }
+
+    #[test]
+    fn distributed_stuffing_detected() {
+        // Arrange
+        let mut detector = BruteForceDetector::new(BruteForceConfig::default(), endpoints());
+
+        // Act
+        for i in 0..100 {
+            detector.add(&entry(
+                &format!("10.0.{}.1", i % 25),
+                i,
+                "POST",
+                "/api/login",
+                403,
+            ));
+        }
+        let (clients, windows) = detector.finish(3);
+
+        // Assert
+        assert!(clients.is_empty());
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].failures, 100);
+        assert_eq!(windows[0].clients, 25);
+        assert_eq!(windows[0].top_clients.len(), 3);
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate, TimeDelta};
+use clap::{ValueEnum, builder::PossibleValue};
+
+use crate::LogEntry;
+
+/// Value of calendar days
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalendarMetric {
+    Requests,
+    /// Share of requests answered with 4xx or 5xx
+    ErrorRate,
+}
+
+impl ValueEnum for CalendarMetric {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[CalendarMetric::Requests, CalendarMetric::ErrorRate]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            CalendarMetric::Requests => PossibleValue::new("requests"),
+            CalendarMetric::ErrorRate => PossibleValue::new("error-rate"),
+        })
+    }
+}
+
+/// Requests and errors of a day
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Day {
+    pub requests: u64,
+    pub errors: u64,
+}
+
+impl Day {
+    /// Value of metric. Error rate is in hundredths of percent to keep precision
+    #[must_use]
+    pub fn value(&self, metric: CalendarMetric) -> u64 {
+        match metric {
+            CalendarMetric::Requests => self.requests,
+            CalendarMetric::ErrorRate if self.requests == 0 => 0,
+            CalendarMetric::ErrorRate => self.errors * 10_000 / self.requests,
+        }
+    }
+}
+
+/// Daily requests and errors laid out into weeks started from Monday
+#[derive(Debug, Default)]
+pub struct Calendar {
+    days: BTreeMap<NaiveDate, Day>,
+}
+
+impl Calendar {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds entry into the day of its date in log time zone (the same as `date` parameter).
+    /// Entries without timestamp are ignored
+    pub fn add(&mut self, entry: &LogEntry) {
+        if !entry.has_timestamp() {
+            return;
+        }
+        let day = self.days.entry(entry.date()).or_default();
+        day.requests += 1;
+        day.errors += u64::from(entry.status >= 400);
+    }
+
+    /// Day statistics or `None` if date is out of log period
+    #[must_use]
+    pub fn day(&self, date: NaiveDate) -> Option<Day> {
+        let (first, last) = self.period()?;
+        (first..=last)
+            .contains(&date)
+            .then(|| self.days.get(&date).copied().unwrap_or_default())
+    }
+
+    /// The first and the last dates of log
+    #[must_use]
+    pub fn period(&self) -> Option<(NaiveDate, NaiveDate)> {
+        Some((
+            *self.days.first_key_value()?.0,
+            *self.days.last_key_value()?.0,
+        ))
+    }
+
+    /// Mondays of all weeks of log period
+    #[must_use]
+    pub fn weeks(&self) -> Vec<NaiveDate> {
+        let Some((first, last)) = self.period() else {
+            return vec![];
+        };
+        let mut week = first - TimeDelta::days(i64::from(first.weekday().num_days_from_monday()));
+        let mut weeks = vec![];
+        while week <= last {
+            weeks.push(week);
+            week += TimeDelta::weeks(1);
+        }
+        weeks
+    }
+
+    /// Days with requests ordered by date
+    pub fn days(&self) -> impl Iterator<Item = (&NaiveDate, &Day)> {
+        self.days.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(timestamp: &str, status: u16) -> LogEntry {
+        LogEntryBuilder::new()
+            .timestamp(timestamp)
+            .status(status)
+            .build()
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn days_counted_in_log_time_zone() {
+        // Arrange
+        let mut calendar = Calendar::new();
+
+        // Act
+        calendar.add(&entry("2024-05-01T23:30:00+03:00", 200));
+        calendar.add(&entry("2024-05-01T10:00:00+03:00", 500));
+        calendar.add(&entry("2024-05-03T00:30:00+03:00", 404));
+        calendar.add(&LogEntry::default());
+
+        // Assert
+        assert_eq!(
+            calendar.day(date("2024-05-01")),
+            Some(Day {
+                requests: 2,
+                errors: 1
+            })
+        );
+        assert_eq!(calendar.day(date("2024-05-02")), Some(Day::default()));
+        assert_eq!(calendar.day(date("2024-04-30")), None);
+        assert_eq!(calendar.days().count(), 2);
+    }
+
+    #[test]
+    fn weeks_start_from_monday() {
+        // Arrange
+        let mut calendar = Calendar::new();
+
+        // Act
+        calendar.add(&entry("2024-05-01T12:00:00Z", 200));
+        calendar.add(&entry("2024-05-13T12:00:00Z", 200));
+
+        // Assert
+        assert_eq!(
+            calendar.weeks(),
+            vec![date("2024-04-29"), date("2024-05-06"), date("2024-05-13")]
+        );
+    }
+
+    #[test]
+    fn error_rate_in_hundredths_of_percent() {
+        // Arrange
+        let day = Day {
+            requests: 3,
+            errors: 1,
+        };
+
+        // Act
+        let actual = day.value(CalendarMetric::ErrorRate);
+
+        // Assert
+        assert_eq!(actual, 3333);
+        assert_eq!(Day::default().value(CalendarMetric::ErrorRate), 0);
+    }
+}
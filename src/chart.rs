@@ -0,0 +1,165 @@
+/// Horizontal bar blocks from one eighth to full character
+const BAR_BLOCKS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+/// Sparkline blocks from lowest to highest
+const SPARK_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// Glyphs used to draw stacked bar segments so that they can be told apart without colors
+pub const STACK_GLYPHS: [char; 6] = ['█', '▓', '▒', '░', '▚', '·'];
+/// Heat levels glyphs from empty to the hottest
+pub const HEAT_GLYPHS: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+/// Creates horizontal bar which length is proportional to `value / max` with one eighth of character precision
+#[must_use]
+pub fn bar(value: u64, max: u64, width: usize) -> String {
+    let eighths = scale(value, max, width * 8);
+    let mut result = "█".repeat(eighths / 8);
+    if !eighths.is_multiple_of(8) {
+        result.push(BAR_BLOCKS[eighths % 8 - 1]);
+    }
+    result
+}
+
+/// Creates horizontal bar that consists of several segments each drawn using its own glyph from `STACK_GLYPHS`
+#[must_use]
+pub fn stacked_bar(values: &[u64], max: u64, width: usize) -> String {
+    let mut result = String::new();
+    let mut cumulative = 0;
+    let mut drawn = 0;
+    for (value, glyph) in values.iter().zip(STACK_GLYPHS.iter().cycle()) {
+        cumulative += value;
+        let boundary = scale(cumulative, max, width);
+        result.extend(std::iter::repeat_n(glyph, boundary - drawn));
+        drawn = boundary;
+    }
+    result
+}
+
+/// Creates sparkline no wider than `width` characters. If there are more values than `width`
+/// adjacent values are summed
+#[must_use]
+pub fn sparkline(values: &[u64], width: usize) -> String {
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+    let chunk = values.len().div_ceil(width);
+    let merged: Vec<u64> = values.chunks(chunk).map(|c| c.iter().sum()).collect();
+    let max = merged.iter().copied().max().unwrap_or_default();
+    merged
+        .iter()
+        .map(|v| {
+            if *v == 0 {
+                ' '
+            } else {
+                let ix = scale(*v, max, SPARK_BLOCKS.len() - 1);
+                SPARK_BLOCKS[ix]
+            }
+        })
+        .collect()
+}
+
+/// Legend of `HEAT_GLYPHS` levels except the empty one with upper bound of each level
+#[must_use]
+pub fn heat_legend(max: u64, format: impl Fn(u64) -> String) -> String {
+    let levels = (HEAT_GLYPHS.len() - 1) as u64;
+    let legend: Vec<String> = (1..=levels)
+        .zip(&HEAT_GLYPHS[1..])
+        .map(|(level, glyph)| format!("{glyph} up to {}", format(max * level / levels)))
+        .collect();
+    legend.join("  ")
+}
+
+/// Index of `HEAT_GLYPHS` for value. Only zero value gets empty level
+#[must_use]
+pub fn heat_level(value: u64, max: u64) -> usize {
+    if value == 0 || max == 0 {
+        0
+    } else {
+        let levels = (HEAT_GLYPHS.len() - 1) as u64;
+        let level = (u128::from(value.min(max)) * u128::from(levels)).div_ceil(u128::from(max));
+        usize::try_from(level).unwrap_or_default()
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn scale(value: u64, max: u64, width: usize) -> usize {
+    if max == 0 {
+        0
+    } else {
+        let scaled = u128::from(value.min(max)) * width as u128 / u128::from(max);
+        scaled as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(0, 10, 4, "")]
+    #[test_case(10, 10, 4, "████")]
+    #[test_case(5, 10, 4, "██")]
+    #[test_case(1, 16, 2, "▏")]
+    #[test_case(3, 4, 1, "▊")]
+    #[test_case(5, 0, 4, "")]
+    fn bar_tests(value: u64, max: u64, width: usize, expected: &str) {
+        // Arrange
+
+        // Act
+        let actual = bar(value, max, width);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn stacked_bar_test() {
+        // Arrange
+        let values = [2, 1, 1];
+
+        // Act
+        let actual = stacked_bar(&values, 8, 8);
+
+        // Assert
+        assert_eq!(actual, "██▓▒");
+    }
+
+    #[test_case(0, 100, 0)]
+    #[test_case(1, 100, 1)]
+    #[test_case(25, 100, 1)]
+    #[test_case(26, 100, 2)]
+    #[test_case(100, 100, 4)]
+    #[test_case(5, 0, 0)]
+    fn heat_level_tests(value: u64, max: u64, expected: usize) {
+        // Arrange
+
+        // Act
+        let actual = heat_level(value, max);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn heat_legend_test() {
+        // Arrange
+
+        // Act
+        let actual = heat_legend(100, |v| v.to_string());
+
+        // Assert
+        assert_eq!(actual, "░ up to 25  ▒ up to 50  ▓ up to 75  █ up to 100");
+    }
+
+    #[test_case(&[], 10, "")]
+    #[test_case(&[0, 1, 7], 10, " ▂█")]
+    #[test_case(&[1, 1, 2, 2], 2, "▄█")]
+    fn sparkline_tests(values: &[u64], width: usize, expected: &str) {
+        // Arrange
+
+        // Act
+        let actual = sparkline(values, width);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, FixedOffset, TimeDelta};
+
+use crate::LogEntry;
+
+/// Request of the client or period without requests
+#[derive(Debug)]
+pub enum ClientEvent {
+    Request(Box<LogEntry>),
+    /// Time between two requests when it is not less than gap configured
+    Gap(TimeDelta),
+}
+
+/// Everything single client did in chronological order with summaries
+#[derive(Debug, Default)]
+pub struct ClientTimeline {
+    pub client: String,
+    pub events: Vec<ClientEvent>,
+    pub requests: u64,
+    pub bytes: u64,
+    pub first_seen: Option<DateTime<FixedOffset>>,
+    pub last_seen: Option<DateTime<FixedOffset>>,
+    /// Requests and bytes by host
+    pub hosts: HashMap<String, (u64, u64)>,
+    pub statuses: HashMap<String, u64>,
+    pub agents: HashMap<String, u64>,
+}
+
+/// Collects log entries of single client
+#[derive(Debug)]
+pub struct ClientTracker {
+    client: String,
+    gap: TimeDelta,
+    entries: Vec<LogEntry>,
+}
+
+impl ClientTracker {
+    /// Creates tracker of client IP. Pauses not shorter than `gap` are marked in timeline
+    #[must_use]
+    pub fn new(client: &str, gap: TimeDelta) -> Self {
+        Self {
+            client: client.to_owned(),
+            gap,
+            entries: vec![],
+        }
+    }
+
+    pub fn add(&mut self, entry: &LogEntry) {
+        if entry.clientip == self.client {
+            self.entries.push(entry.clone());
+        }
+    }
+
+    #[must_use]
+    pub fn finish(mut self) -> ClientTimeline {
+        self.entries
+            .sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then(a.line.cmp(&b.line)));
+        let mut timeline = ClientTimeline {
+            first_seen: self.entries.first().map(|e| e.timestamp),
+            last_seen: self.entries.last().map(|e| e.timestamp),
+            client: self.client,
+            ..Default::default()
+        };
+        let mut previous = None;
+        for entry in self.entries {
+            timeline.requests += 1;
+            timeline.bytes += entry.length;
+            let host = timeline.hosts.entry(entry.host.clone()).or_default();
+            host.0 += 1;
+            host.1 += entry.length;
+            *timeline
+                .statuses
+                .entry(entry.status.to_string())
+                .or_default() += 1;
+            *timeline.agents.entry(entry.agent.clone()).or_default() += 1;
+
+            if let Some(previous) = previous {
+                let pause = entry.timestamp - previous;
+                if pause >= self.gap {
+                    timeline.events.push(ClientEvent::Gap(pause));
+                }
+            }
+            previous = Some(entry.timestamp);
+            timeline.events.push(ClientEvent::Request(Box::new(entry)));
+        }
+        timeline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(client: &str, minutes: i64, line: u64, status: u16) -> LogEntry {
+        LogEntryBuilder::new()
+            .client(client)
+            .host("example.com")
+            .agent("curl/8.0")
+            .length(100)
+            .status(status)
+            .line(line)
+            .after(TimeDelta::minutes(minutes))
+            .build()
+    }
+
+    #[test]
+    fn client_requests_ordered_with_gaps() {
+        // Arrange
+        let mut tracker = ClientTracker::new("10.0.0.1", TimeDelta::minutes(30));
+
+        // Act
+        tracker.add(&entry("10.0.0.1", 60, 3, 404));
+        tracker.add(&entry("10.0.0.2", 1, 4, 200));
+        tracker.add(&entry("10.0.0.1", 0, 1, 200));
+        tracker.add(&entry("10.0.0.1", 10, 2, 200));
+        let actual = tracker.finish();
+
+        // Assert
+        let events: Vec<_> = actual
+            .events
+            .iter()
+            .map(|e| match e {
+                ClientEvent::Request(entry) => format!("line {}", entry.line),
+                ClientEvent::Gap(pause) => format!("gap {}", pause.num_minutes()),
+            })
+            .collect();
+        assert_eq!(events, vec!["line 1", "line 2", "gap 50", "line 3"]);
+        assert_eq!(actual.requests, 3);
+        assert_eq!(actual.bytes, 300);
+        assert_eq!(actual.hosts["example.com"], (3, 300));
+        assert_eq!(actual.statuses["200"], 2);
+        assert_eq!(actual.statuses["404"], 1);
+        assert_eq!(actual.agents["curl/8.0"], 3);
+        assert_eq!(
+            actual.last_seen.zip(actual.first_seen).map(|(l, f)| l - f),
+            Some(TimeDelta::hours(1))
+        );
+    }
+
+    #[test]
+    fn unknown_client_empty() {
+        // Arrange
+        let mut tracker = ClientTracker::new("10.0.0.1", TimeDelta::minutes(30));
+
+        // Act
+        tracker.add(&entry("10.0.0.2", 0, 1, 200));
+        let actual = tracker.finish();
+
+        // Assert
+        assert_eq!(actual.requests, 0);
+        assert!(actual.events.is_empty());
+        assert!(actual.first_seen.is_none());
+    }
+}
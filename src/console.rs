@@ -1,12 +1,51 @@
+use chrono::{Datelike, TimeDelta};
 use comfy_table::presets::UTF8_HORIZONTAL_ONLY;
-use comfy_table::{Attribute, Cell, ContentArrangement, Table};
+use comfy_table::{Attribute, Cell, Color, ContentArrangement, Table};
 use core::hash::Hash;
+use indicatif::HumanBytes;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::pin::pin;
 use tokio_stream::Stream;
 use tokio_stream::StreamExt;
 
-use crate::{GroupedParameter, LogEntry, LogParameter, calculate_percent};
+use crate::anomaly::{Anomaly, Metric};
+use crate::brokenlink::BrokenLinks;
+use crate::bruteforce::{SuspiciousClient, SuspiciousWindow};
+use crate::calendar::{Calendar, CalendarMetric};
+use crate::chart::{
+    HEAT_GLYPHS, STACK_GLYPHS, bar, heat_legend, heat_level, sparkline, stacked_bar,
+};
+use crate::client::{ClientEvent, ClientTimeline};
+use crate::heatmap::{HOURS, Heatmap, HeatmapMetric, WEEKDAYS};
+use crate::hotlink::HotlinkSummary;
+use crate::outage::{Outage, OutageKind, OutageScope};
+use crate::pivot::Crosstab;
+use crate::ratelimit::{REJECTION_SLOTS, RateLimitSummary};
+use crate::redirect::{HostRedirects, REDIRECT_STATUSES, RedirectSummary};
+use crate::scanner::Scanner;
+use crate::session::SessionsSummary;
+use crate::signature::RuleMatches;
+use crate::timeline::Timeline;
+use crate::visitors::VisitorsSummary;
+use crate::{GroupedParameter, LogEntry, LogParameter, STATUS_CLASSES, calculate_percent};
+
+/// Output width used when terminal width cannot be detected
+const DEFAULT_WIDTH: u16 = 120;
+/// Calendar day inside log period without requests
+const NO_REQUESTS_GLYPH: char = '·';
+/// Calendar day with requests which metric value is zero like error rate of a day without errors
+const ZERO_GLYPH: char = '○';
+
+/// Colors of `HEAT_GLYPHS` levels
+const HEAT_COLORS: [Color; 5] = [
+    Color::Reset,
+    Color::DarkGreen,
+    Color::DarkYellow,
+    Color::Red,
+    Color::DarkRed,
+];
 
 /// Prints results table
 pub async fn print(data: impl Stream<Item = LogEntry>) {
@@ -30,13 +69,7 @@ pub async fn print(data: impl Stream<Item = LogEntry>) {
 
     let mut total = 0u64;
     while let Some(entry) = data.next().await {
-        let status = if entry.status >= 400 {
-            Cell::new(entry.status).fg(comfy_table::Color::DarkRed)
-        } else if entry.status >= 300 && entry.status < 400 {
-            Cell::new(entry.status).fg(comfy_table::Color::DarkYellow)
-        } else {
-            Cell::new(entry.status).fg(comfy_table::Color::DarkGreen)
-        };
+        let status = colorize_status(Cell::new(entry.status), entry.status);
 
         table.add_row([
             Cell::new(entry.line),
@@ -58,64 +91,1257 @@ pub async fn print(data: impl Stream<Item = LogEntry>) {
     }
 }
 
-pub fn print_grouped<T: Display + Hash + Eq>(
-    parameter: LogParameter,
-    data: impl Iterator<Item = GroupedParameter<T>>,
-    limit: Option<&usize>,
-) {
-    let parameter_name = match parameter {
+fn terminal_width() -> usize {
+    usize::from(Table::new().width().unwrap_or(DEFAULT_WIDTH))
+}
+
+fn colorize_status(cell: Cell, status: u16) -> Cell {
+    if status >= 400 {
+        cell.fg(Color::DarkRed)
+    } else if status >= 300 {
+        cell.fg(Color::DarkYellow)
+    } else if status > 0 {
+        cell.fg(Color::DarkGreen)
+    } else {
+        cell
+    }
+}
+
+/// Creates cell for parameter value. Status and status class values are colored the same way
+fn parameter_cell(parameter: &LogParameter, value: &str) -> Cell {
+    let status = match parameter {
+        LogParameter::Status => value.parse().ok(),
+        LogParameter::StatusClass => value
+            .chars()
+            .next()
+            .and_then(|c| c.to_digit(10))
+            .and_then(|d| u16::try_from(d * 100).ok()),
+        _ => None,
+    };
+    let cell = Cell::new(value);
+    match status {
+        Some(status) => colorize_status(cell, status),
+        None => cell,
+    }
+}
+
+fn display_name(parameter: &LogParameter) -> Cow<'static, str> {
+    Cow::Borrowed(match parameter {
         LogParameter::Time => "Time",
         LogParameter::Date => "Date",
         LogParameter::Agent => "User agent",
         LogParameter::ClientIp => "Client IP",
         LogParameter::Status => "HTTP Status",
+        LogParameter::StatusClass => "Status class",
         LogParameter::Method => "HTTP Method",
         LogParameter::Schema => "Schema",
         LogParameter::Request => "Request URI",
+        LogParameter::Endpoint => "Endpoint",
+        LogParameter::Template => "URL template",
+        LogParameter::Path => "Path",
+        LogParameter::QueryKeys => "Query keys",
+        LogParameter::Query(name) => return Cow::Owned(format!("Query {name}")),
         LogParameter::Referrer => "Referrer",
+        LogParameter::ReferrerDomain => "Referrer domain",
+        LogParameter::ReferrerType => "Referrer type",
+        LogParameter::Host => "Host",
+    })
+}
+
+/// Header of grouped parameters table with optional error, distinct values and chart columns
+fn grouped_header(
+    parameter_name: &str,
+    distinct: Option<&LogParameter>,
+    approximated: bool,
+    chart: bool,
+) -> Vec<Cell> {
+    let mut header = vec![
+        Cell::new(parameter_name).add_attribute(Attribute::Bold),
+        Cell::new("Count").add_attribute(Attribute::Bold),
+    ];
+    if approximated {
+        header.push(Cell::new("Error ±").add_attribute(Attribute::Bold));
+    }
+    header.push(Cell::new("Proportion").add_attribute(Attribute::Bold));
+    header.push(Cell::new("Cumulative").add_attribute(Attribute::Bold));
+    if let Some(distinct) = distinct {
+        header.push(
+            Cell::new(format!("Distinct {} (approx.)", display_name(distinct)))
+                .add_attribute(Attribute::Bold),
+        );
+    }
+    if chart {
+        header.push(Cell::new("Chart").add_attribute(Attribute::Bold));
+    }
+    header
+}
+
+/// Prints grouped parameters table. If `distinct` is specified
+/// the estimated number of unique values of this parameter within each group is displayed too.
+/// `approximated_total` is the number of all grouped entries when `data` contains approximate counts
+/// of the most frequent groups only. If `chart` is set a horizontal bar is drawn next to each row
+pub fn print_grouped<T: Display + Hash + Eq>(
+    parameter: &LogParameter,
+    distinct: Option<&LogParameter>,
+    data: impl Iterator<Item = GroupedParameter<T>>,
+    limit: Option<&usize>,
+    approximated_total: Option<u64>,
+    chart: bool,
+) {
+    let parameter_name = display_name(parameter);
+    let approximated = approximated_total.is_some();
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header(grouped_header(
+            &parameter_name,
+            distinct,
+            approximated,
+            chart,
+        ))
+        .set_content_arrangement(ContentArrangement::Dynamic);
+
+    let mut data: Vec<_> = data.collect();
+    data.sort_unstable_by(|a, b| Ord::cmp(&b.count, &a.count));
+
+    // Proportions are calculated against all groups not only the displayed ones
+    let total_count: u64 = approximated_total.unwrap_or_else(|| data.iter().map(|e| e.count).sum());
+    let total = data.len();
+    let limit = *limit.unwrap_or(&usize::MAX);
+    let max_count = data.first().map(|e| e.count).unwrap_or_default();
+    let chart_width = (terminal_width() / 4).clamp(10, 50);
+
+    let mut cumulative = 0u64;
+    for entry in data.iter().take(limit) {
+        // approximated counts may be overestimated so never go beyond total
+        cumulative = (cumulative + entry.count).min(total_count);
+        let mut row = vec![
+            parameter_cell(parameter, &entry.parameter.to_string()),
+            Cell::new(entry.count),
+        ];
+        if approximated {
+            row.push(Cell::new(entry.error.unwrap_or_default()));
+        }
+        row.push(Cell::new(format!(
+            "{:.2}%",
+            calculate_percent(entry.count, total_count)
+        )));
+        row.push(Cell::new(format!(
+            "{:.2}%",
+            calculate_percent(cumulative, total_count)
+        )));
+        if let Some(distinct) = entry.distinct {
+            row.push(Cell::new(distinct));
+        }
+        if chart {
+            row.push(Cell::new(bar(entry.count, max_count, chart_width)));
+        }
+        table.add_row(row);
+    }
+
+    let other_count = total_count - cumulative;
+    let other_values = total.saturating_sub(limit);
+    if other_values > 0 || (approximated && other_count > 0) {
+        let title = if approximated {
+            "(other values)".to_owned()
+        } else {
+            format!("(other {other_values} values)")
+        };
+        let mut row = vec![
+            Cell::new(title).add_attribute(Attribute::Italic),
+            Cell::new(other_count),
+        ];
+        if approximated {
+            row.push(Cell::new(""));
+        }
+        row.push(Cell::new(format!(
+            "{:.2}%",
+            calculate_percent(other_count, total_count)
+        )));
+        row.push(Cell::new(format!(
+            "{:.2}%",
+            calculate_percent(total_count, total_count)
+        )));
+        if chart {
+            if distinct.is_some() {
+                row.push(Cell::new(""));
+            }
+            row.push(Cell::new(bar(other_count, max_count, chart_width)));
+        }
+        table.add_row(row);
+    }
+
+    if total > 0 {
+        println!("{table}");
+        let group = if parameter_name.chars().last().unwrap_or_default() == 's' {
+            format!("{parameter_name}es")
+        } else {
+            format!("{parameter_name}s")
+        };
+        let title = if approximated {
+            format!("Tracked {group}:")
+        } else {
+            format!("Total {group}:")
+        };
+        let data_title = "Total data:";
+        let width = title.len().max(data_title.len());
+        println!("{title:<width$} {total}");
+        println!("{data_title:<width$} {total_count}");
+        if approximated {
+            println!("Counts are approximate: the real count is between Count - Error and Count");
+        }
+    }
+}
+
+/// Prints crosstab as a matrix with row and column totals
+pub fn print_pivot(
+    rows: &LogParameter,
+    columns: &LogParameter,
+    crosstab: &Crosstab,
+    limit: Option<&usize>,
+) {
+    if crosstab.total() == 0 {
+        return;
+    }
+    let mut header = vec![
+        Cell::new(format!(
+            "{} \\ {}",
+            display_name(rows),
+            display_name(columns)
+        ))
+        .add_attribute(Attribute::Bold),
+    ];
+    header.extend(
+        crosstab
+            .columns()
+            .map(|(column, _)| parameter_cell(columns, column).add_attribute(Attribute::Bold)),
+    );
+    header.push(Cell::new("Total").add_attribute(Attribute::Bold));
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header(header)
+        .set_content_arrangement(ContentArrangement::Dynamic);
+
+    let all_rows = crosstab.rows();
+    let limit = *limit.unwrap_or(&usize::MAX);
+    for row in all_rows.iter().take(limit) {
+        let mut cells = vec![parameter_cell(rows, row.key)];
+        cells.extend(row.cells.iter().map(Cell::new));
+        cells.push(Cell::new(row.total).add_attribute(Attribute::Bold));
+        table.add_row(cells);
+    }
+
+    let other_rows = &all_rows[all_rows.len().min(limit)..];
+    if !other_rows.is_empty() {
+        let mut cells = vec![
+            Cell::new(format!("(other {} values)", other_rows.len()))
+                .add_attribute(Attribute::Italic),
+        ];
+        cells.extend(
+            (0..crosstab.columns().count())
+                .map(|i| Cell::new(other_rows.iter().map(|r| r.cells[i]).sum::<u64>())),
+        );
+        cells.push(
+            Cell::new(other_rows.iter().map(|r| r.total).sum::<u64>())
+                .add_attribute(Attribute::Bold),
+        );
+        table.add_row(cells);
+    }
+
+    let mut totals = vec![Cell::new("Total").add_attribute(Attribute::Bold)];
+    totals.extend(
+        crosstab
+            .columns()
+            .map(|(_, total)| Cell::new(total).add_attribute(Attribute::Bold)),
+    );
+    totals.push(Cell::new(crosstab.total()).add_attribute(Attribute::Bold));
+    table.add_row(totals);
+
+    println!("{table}");
+}
+
+/// Prints requests per time bucket as a sparkline and a bar chart.
+/// If `split` is set bars are split by status class
+pub fn print_timeline(timeline: &Timeline, split: bool) {
+    let buckets = timeline.buckets();
+    if buckets.is_empty() {
+        return;
+    }
+    let bucket = timeline.bucket();
+    let format = if bucket.num_days() > 0 {
+        "%Y-%m-%d"
+    } else if bucket.num_minutes() > 0 {
+        "%Y-%m-%d %H:%M"
+    } else {
+        "%Y-%m-%d %H:%M:%S"
+    };
+
+    let totals: Vec<u64> = buckets.iter().map(crate::timeline::Bucket::total).collect();
+    let max = totals.iter().copied().max().unwrap_or_default();
+    let width = terminal_width();
+
+    let title = format!("Requests per {}: ", humanize_duration(bucket));
+    let spark = sparkline(&totals, width.saturating_sub(title.chars().count() + 1));
+    println!("{title}{spark}");
+    if timeline.is_enlarged() {
+        println!(
+            "Bucket is enlarged to {} to fit log period",
+            humanize_duration(bucket)
+        );
+    }
+
+    // classes that are present in the timeline
+    let classes: Vec<usize> = (0..STATUS_CLASSES.len())
+        .filter(|i| split && buckets.iter().any(|b| b.classes[*i] > 0))
+        .collect();
+
+    let mut header = vec![
+        Cell::new("Time").add_attribute(Attribute::Bold),
+        Cell::new("Requests").add_attribute(Attribute::Bold),
+    ];
+    for i in &classes {
+        header.push(
+            parameter_cell(&LogParameter::StatusClass, STATUS_CLASSES[*i])
+                .add_attribute(Attribute::Bold),
+        );
+    }
+    header.push(Cell::new("Chart").add_attribute(Attribute::Bold));
+
+    let label_width = buckets[0].start.format(format).to_string().len();
+    let used = label_width + max.to_string().len().max(8) + classes.len() * 10 + 12;
+    let chart_width = width.saturating_sub(used).max(10);
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header(header)
+        .set_content_arrangement(ContentArrangement::Disabled);
+
+    for b in &buckets {
+        let mut row = vec![Cell::new(b.start.format(format)), Cell::new(b.total())];
+        row.extend(classes.iter().map(|i| Cell::new(b.classes[*i])));
+        let chart = if split {
+            stacked_bar(&b.classes, max, chart_width)
+        } else {
+            bar(b.total(), max, chart_width)
+        };
+        row.push(Cell::new(chart));
+        table.add_row(row);
+    }
+    println!("{table}");
+
+    if split {
+        let legend: Vec<String> = classes
+            .iter()
+            .map(|i| format!("{} {}", STACK_GLYPHS[*i], STATUS_CLASSES[*i]))
+            .collect();
+        println!("Legend: {}", legend.join("  "));
+    }
+    println!("Total data: {}", totals.iter().sum::<u64>());
+}
+
+fn humanize_duration(duration: chrono::TimeDelta) -> String {
+    let seconds = duration.num_seconds();
+    let (value, unit) = if seconds % 604_800 == 0 {
+        (seconds / 604_800, "week")
+    } else if seconds % 86_400 == 0 {
+        (seconds / 86_400, "day")
+    } else if seconds % 3_600 == 0 {
+        (seconds / 3_600, "hour")
+    } else if seconds % 60 == 0 {
+        (seconds / 60, "minute")
+    } else {
+        (seconds, "second")
     };
+    if value == 1 {
+        unit.to_owned()
+    } else {
+        format!("{value} {unit}s")
+    }
+}
+
+/// Prints visitor sessions statistics and the most frequent entry and exit paths
+pub fn print_sessions(summary: &SessionsSummary, limit: usize) {
+    if summary.count == 0 {
+        return;
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Metric").add_attribute(Attribute::Bold),
+            Cell::new("Value").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    table.add_row([Cell::new("Visitors"), Cell::new(summary.visitors)]);
+    table.add_row([Cell::new("Sessions"), Cell::new(summary.count)]);
+    table.add_row([
+        Cell::new("Average duration"),
+        Cell::new(format_duration(summary.average_duration())),
+    ]);
+    table.add_row([
+        Cell::new("Pages per session"),
+        Cell::new(format!("{:.2}", summary.pages_per_session())),
+    ]);
+    table.add_row([
+        Cell::new("Bounce rate"),
+        Cell::new(format!(
+            "{:.2}%",
+            calculate_percent(summary.bounces, summary.count)
+        )),
+    ]);
+    println!("{table}");
+
+    print_counts("Entry path", &summary.entry_paths, limit);
+    print_counts("Exit path", &summary.exit_paths, limit);
+}
+
+/// Prints the biggest `limit` counts with their proportion
+fn print_counts(title: &str, counts: &HashMap<String, u64>, limit: usize) {
+    let total: u64 = counts.values().sum();
+    let mut counts: Vec<_> = counts.iter().collect();
+    counts.sort_unstable_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
 
     let mut table = Table::new();
     table
         .load_preset(UTF8_HORIZONTAL_ONLY)
         .set_header([
-            Cell::new(parameter_name).add_attribute(Attribute::Bold),
+            Cell::new(title).add_attribute(Attribute::Bold),
             Cell::new("Count").add_attribute(Attribute::Bold),
             Cell::new("Proportion").add_attribute(Attribute::Bold),
         ])
         .set_content_arrangement(ContentArrangement::Dynamic);
+    for (key, count) in counts.into_iter().take(limit) {
+        table.add_row([
+            Cell::new(key),
+            Cell::new(count),
+            Cell::new(format!("{:.2}%", calculate_percent(*count, total))),
+        ]);
+    }
+    println!("{table}");
+}
 
-    let mut data: Vec<_> = data.collect();
-    data.sort_unstable_by(|a, b| Ord::cmp(&b.count, &a.count));
+/// Formats duration like `1h 02m 03s`
+fn format_duration(duration: chrono::TimeDelta) -> String {
+    let seconds = duration.num_seconds();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m {seconds:02}s")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds:02}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
 
-    let limited: Vec<_> = data
-        .into_iter()
-        .take(*limit.unwrap_or(&usize::MAX))
-        .collect();
+/// Prints anomalous time buckets with their top contributing clients and paths
+pub fn print_anomalies(anomalies: &[Anomaly]) {
+    if anomalies.is_empty() {
+        println!("No anomalies found");
+        return;
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Window").add_attribute(Attribute::Bold),
+            Cell::new("Scope").add_attribute(Attribute::Bold),
+            Cell::new("Metric").add_attribute(Attribute::Bold),
+            Cell::new("Value").add_attribute(Attribute::Bold),
+            Cell::new("Baseline").add_attribute(Attribute::Bold),
+            Cell::new("Score").add_attribute(Attribute::Bold),
+            Cell::new("Top clients").add_attribute(Attribute::Bold),
+            Cell::new("Top paths").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
 
-    let total_count: u64 = limited.iter().map(|e| e.count).sum();
+    for anomaly in anomalies {
+        let format_value = |value: f64| match anomaly.metric {
+            Metric::Requests => format!("{value:.0}"),
+            Metric::ErrorRate => format!("{:.2}%", value * 100.0),
+            #[allow(clippy::cast_possible_truncation)]
+            #[allow(clippy::cast_sign_loss)]
+            Metric::Bytes => HumanBytes(value as u64).to_string(),
+        };
+        let score = Cell::new(format!("{:+.1}", anomaly.score));
+        let score = if anomaly.score > 0.0 {
+            score.fg(Color::DarkRed)
+        } else {
+            score.fg(Color::DarkYellow)
+        };
+        table.add_row([
+            Cell::new(anomaly.start.format("%Y-%m-%d %H:%M")),
+            Cell::new(&anomaly.scope),
+            Cell::new(anomaly.metric),
+            Cell::new(format_value(anomaly.value)),
+            Cell::new(format_value(anomaly.baseline)),
+            score,
+            Cell::new(format_top(&anomaly.top_clients)),
+            Cell::new(format_top(&anomaly.top_paths)),
+        ]);
+    }
+    println!("{table}");
+    println!("Total anomalies: {}", anomalies.len());
+}
 
-    for entry in limited {
+fn format_top(top: &[(String, u64)]) -> String {
+    top.iter()
+        .map(|(key, count)| format!("{key} ({count})"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Prints clients suspected in vulnerability scanning
+pub fn print_scanners(scanners: &[Scanner], limit: usize) {
+    if scanners.is_empty() {
+        println!("No scanners found");
+        return;
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Client IP").add_attribute(Attribute::Bold),
+            Cell::new("Score").add_attribute(Attribute::Bold),
+            Cell::new("Requests").add_attribute(Attribute::Bold),
+            Cell::new("Probes").add_attribute(Attribute::Bold),
+            Cell::new("404").add_attribute(Attribute::Bold),
+            Cell::new("Burst").add_attribute(Attribute::Bold),
+            Cell::new("First seen").add_attribute(Attribute::Bold),
+            Cell::new("Last seen").add_attribute(Attribute::Bold),
+            Cell::new("Sample requests").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+
+    for scanner in scanners.iter().take(limit) {
         table.add_row([
-            Cell::new(entry.parameter),
-            Cell::new(entry.count),
+            Cell::new(&scanner.client),
+            Cell::new(format!("{:.1}", scanner.score)).fg(Color::DarkRed),
+            Cell::new(scanner.requests),
+            Cell::new(scanner.probes),
+            Cell::new(scanner.not_found),
+            Cell::new(scanner.burst),
+            Cell::new(scanner.first_seen),
+            Cell::new(scanner.last_seen),
+            Cell::new(scanner.samples.join("\n")),
+        ]);
+    }
+    println!("{table}");
+    println!("Total scanners: {}", scanners.len());
+}
+
+/// Prints paths answered with 404 or 410 along with internal pages that link to them
+pub fn print_broken_links(broken: &BrokenLinks, limit: usize, referrers: usize) {
+    let links = &broken.links;
+    if links.is_empty() {
+        println!("No broken links found");
+        print_scanner_hits(broken.scanner_hits);
+        return;
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Path").add_attribute(Attribute::Bold),
+            Cell::new("Status").add_attribute(Attribute::Bold),
+            Cell::new("Hits").add_attribute(Attribute::Bold),
+            Cell::new("Clients").add_attribute(Attribute::Bold),
+            Cell::new("Internal").add_attribute(Attribute::Bold),
+            Cell::new("External").add_attribute(Attribute::Bold),
+            Cell::new("Direct").add_attribute(Attribute::Bold),
+            Cell::new("Linked from").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+
+    for link in links.iter().take(limit) {
+        let statuses: Vec<_> = link.statuses.iter().map(u16::to_string).collect();
+        let linked_from: Vec<_> = link
+            .referrers
+            .iter()
+            .take(referrers)
+            .map(|(referrer, hits)| format!("{referrer} ({hits})"))
+            .collect();
+        table.add_row([
+            Cell::new(&link.path),
+            Cell::new(statuses.join(", ")),
+            Cell::new(link.hits),
+            Cell::new(link.clients),
+            Cell::new(link.internal_hits).fg(Color::DarkRed),
+            Cell::new(link.external_hits),
+            Cell::new(link.direct_hits),
+            Cell::new(linked_from.join("\n")),
+        ]);
+    }
+    println!("{table}");
+    println!("Total broken links: {}", links.len());
+    print_scanner_hits(broken.scanner_hits);
+}
+
+fn print_scanner_hits(hits: u64) {
+    if hits > 0 {
+        println!("Excluded hits of scanners: {hits}");
+    }
+}
+
+/// Prints attack signatures matches summary and sample matches of each rule
+pub fn print_attacks(matches: &[RuleMatches]) {
+    if matches.is_empty() {
+        println!("No attack signatures found");
+        return;
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Rule").add_attribute(Attribute::Bold),
+            Cell::new("Hits").add_attribute(Attribute::Bold),
+            Cell::new("Clients").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    for m in matches {
+        table.add_row([
+            Cell::new(&m.rule).fg(Color::DarkRed),
+            Cell::new(m.hits),
+            Cell::new(m.clients.len()),
+        ]);
+    }
+    println!("{table}");
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Rule").add_attribute(Attribute::Bold),
+            Cell::new("Time").add_attribute(Attribute::Bold),
+            Cell::new("Client IP").add_attribute(Attribute::Bold),
+            Cell::new("Status").add_attribute(Attribute::Bold),
+            Cell::new("Field").add_attribute(Attribute::Bold),
+            Cell::new("Value").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    for m in matches {
+        for sample in &m.samples {
+            table.add_row([
+                Cell::new(&m.rule),
+                Cell::new(sample.time),
+                Cell::new(&sample.client),
+                colorize_status(Cell::new(sample.status), sample.status),
+                Cell::new(sample.field),
+                Cell::new(&sample.value),
+            ]);
+        }
+    }
+    println!("{table}");
+    println!(
+        "Total matches: {}",
+        matches.iter().map(|m| m.hits).sum::<u64>()
+    );
+}
+
+/// Prints clients and time windows suspected in brute forcing of login endpoints
+pub fn print_bruteforce(clients: &[SuspiciousClient], windows: &[SuspiciousWindow], limit: usize) {
+    if clients.is_empty() && windows.is_empty() {
+        println!("No brute force attempts found");
+        return;
+    }
+    if !clients.is_empty() {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_HORIZONTAL_ONLY)
+            .set_header([
+                Cell::new("Client IP").add_attribute(Attribute::Bold),
+                Cell::new("Max failures in window").add_attribute(Attribute::Bold),
+                Cell::new("Failures").add_attribute(Attribute::Bold),
+                Cell::new("Successes").add_attribute(Attribute::Bold),
+                Cell::new("First seen").add_attribute(Attribute::Bold),
+                Cell::new("Last seen").add_attribute(Attribute::Bold),
+                Cell::new("Endpoints").add_attribute(Attribute::Bold),
+            ])
+            .set_content_arrangement(ContentArrangement::Dynamic);
+        for client in clients.iter().take(limit) {
+            let successes = Cell::new(client.successes);
+            table.add_row([
+                Cell::new(&client.client),
+                Cell::new(client.max_failures).fg(Color::DarkRed),
+                Cell::new(client.failures),
+                if client.successes > 0 {
+                    successes.fg(Color::DarkYellow)
+                } else {
+                    successes
+                },
+                Cell::new(client.first_seen),
+                Cell::new(client.last_seen),
+                Cell::new(
+                    client
+                        .endpoints
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ),
+            ]);
+        }
+        println!("{table}");
+        println!("Total suspicious clients: {}", clients.len());
+    }
+    if !windows.is_empty() {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_HORIZONTAL_ONLY)
+            .set_header([
+                Cell::new("Window start").add_attribute(Attribute::Bold),
+                Cell::new("Endpoint").add_attribute(Attribute::Bold),
+                Cell::new("Failures").add_attribute(Attribute::Bold),
+                Cell::new("Clients").add_attribute(Attribute::Bold),
+                Cell::new("Top clients").add_attribute(Attribute::Bold),
+            ])
+            .set_content_arrangement(ContentArrangement::Dynamic);
+        for window in windows.iter().take(limit) {
+            table.add_row([
+                Cell::new(window.start),
+                Cell::new(&window.endpoint),
+                Cell::new(window.failures).fg(Color::DarkRed),
+                Cell::new(window.clients),
+                Cell::new(format_top(&window.top_clients)),
+            ]);
+        }
+        println!("{table}");
+        println!("Total suspicious windows: {}", windows.len());
+    }
+}
+
+/// Prints requests that would be rejected or delayed by rate limiting
+pub fn print_ratelimit(summary: &RateLimitSummary, limit: usize) {
+    if summary.requests == 0 {
+        return;
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Requests").add_attribute(Attribute::Bold),
+            Cell::new("Rejected").add_attribute(Attribute::Bold),
+            Cell::new("Delayed").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    table.add_row([
+        Cell::new(summary.requests),
+        Cell::new(format!(
+            "{} ({:.2}%)",
+            summary.rejected,
+            calculate_percent(summary.rejected, summary.requests)
+        ))
+        .fg(Color::DarkRed),
+        Cell::new(format!(
+            "{} ({:.2}%)",
+            summary.delayed,
+            calculate_percent(summary.delayed, summary.requests)
+        ))
+        .fg(Color::DarkYellow),
+    ]);
+    println!("{table}");
+    if summary.keys.is_empty() {
+        return;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Key").add_attribute(Attribute::Bold),
+            Cell::new("Requests").add_attribute(Attribute::Bold),
+            Cell::new("Rejected").add_attribute(Attribute::Bold),
+            Cell::new("Delayed").add_attribute(Attribute::Bold),
+            Cell::new("First rejected").add_attribute(Attribute::Bold),
+            Cell::new("Last rejected").add_attribute(Attribute::Bold),
+            Cell::new("Rejections over time").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    for key in summary.keys.iter().take(limit) {
+        table.add_row([
+            Cell::new(&key.key),
+            Cell::new(key.requests),
+            Cell::new(key.rejected).fg(Color::DarkRed),
+            Cell::new(key.delayed).fg(Color::DarkYellow),
+            Cell::new(
+                key.first_rejected
+                    .map(|t| t.to_string())
+                    .unwrap_or_default(),
+            ),
+            Cell::new(key.last_rejected.map(|t| t.to_string()).unwrap_or_default()),
+            Cell::new(sparkline(&key.rejections, REJECTION_SLOTS)),
+        ]);
+    }
+    println!("{table}");
+    println!("Total limited keys: {}", summary.keys.len());
+}
+
+/// Prints static assets traffic served to other sites by referring domain
+pub fn print_hotlinks(summary: &HotlinkSummary, limit: usize) {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Total traffic").add_attribute(Attribute::Bold),
+            Cell::new("Static assets").add_attribute(Attribute::Bold),
+            Cell::new("Hotlinked").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    table.add_row([
+        Cell::new(HumanBytes(summary.total_bytes)),
+        Cell::new(HumanBytes(summary.asset_bytes)),
+        Cell::new(format!(
+            "{} ({:.2}%)",
+            HumanBytes(summary.hotlinked_bytes),
+            calculate_percent(summary.hotlinked_bytes, summary.total_bytes)
+        ))
+        .fg(Color::DarkRed),
+    ]);
+    println!("{table}");
+    if summary.domains.is_empty() {
+        return;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Referring domain").add_attribute(Attribute::Bold),
+            Cell::new("Bytes").add_attribute(Attribute::Bold),
+            Cell::new("Share").add_attribute(Attribute::Bold),
+            Cell::new("Requests").add_attribute(Attribute::Bold),
+            Cell::new("Assets").add_attribute(Attribute::Bold),
+            Cell::new("Top asset").add_attribute(Attribute::Bold),
+            Cell::new("Hosts").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    for domain in summary.domains.iter().take(limit) {
+        table.add_row([
+            Cell::new(&domain.domain),
+            Cell::new(HumanBytes(domain.bytes)).fg(Color::DarkRed),
             Cell::new(format!(
                 "{:.2}%",
-                calculate_percent(entry.count, total_count)
+                calculate_percent(domain.bytes, summary.hotlinked_bytes)
             )),
+            Cell::new(domain.requests),
+            Cell::new(domain.assets),
+            Cell::new(format!(
+                "{} ({})",
+                domain.top_asset,
+                HumanBytes(domain.top_asset_bytes)
+            )),
+            Cell::new(domain.hosts.join(", ")),
         ]);
     }
+    println!("{table}");
+    println!("Total hotlinking domains: {}", summary.domains.len());
+}
 
-    let total = table.row_count();
-    if total > 0 {
-        println!("{table}");
-        let group = if parameter_name.chars().last().unwrap_or_default() == 's' {
-            format!("{parameter_name}es")
+/// Prints redirects share of each host and the paths with the most redirects
+pub fn print_redirects(summary: &RedirectSummary, limit: usize) {
+    if summary.total.requests == 0 {
+        return;
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Host").add_attribute(Attribute::Bold),
+            Cell::new("Requests").add_attribute(Attribute::Bold),
+            Cell::new("Redirects").add_attribute(Attribute::Bold),
+            Cell::new("HTTP requests").add_attribute(Attribute::Bold),
+            Cell::new("HTTP redirected").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    for host in &summary.hosts {
+        table.add_row(host_redirects_row(&host.host, host));
+    }
+    table.add_row(
+        host_redirects_row("Total", &summary.total)
+            .into_iter()
+            .map(|c| c.add_attribute(Attribute::Bold)),
+    );
+    println!("{table}");
+    if summary.paths.is_empty() {
+        println!("No redirects found");
+        return;
+    }
+
+    let mut header = vec![
+        Cell::new("Host").add_attribute(Attribute::Bold),
+        Cell::new("Path").add_attribute(Attribute::Bold),
+        Cell::new("Requests since first redirect").add_attribute(Attribute::Bold),
+        Cell::new("Redirects").add_attribute(Attribute::Bold),
+    ];
+    header.extend(
+        REDIRECT_STATUSES
+            .iter()
+            .map(|s| Cell::new(s).add_attribute(Attribute::Bold)),
+    );
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header(header)
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    for path in summary.paths.iter().take(limit) {
+        let mut row = vec![
+            Cell::new(&path.host),
+            Cell::new(&path.path),
+            Cell::new(path.requests),
+            Cell::new(format!(
+                "{} ({:.2}%)",
+                path.redirects,
+                calculate_percent(path.redirects, path.requests)
+            ))
+            .fg(Color::DarkYellow),
+        ];
+        row.extend(path.statuses.iter().map(Cell::new));
+        table.add_row(row);
+    }
+    println!("{table}");
+    println!("Total redirected paths: {}", summary.paths.len());
+}
+
+/// Plain `http` requests left without redirect are highlighted since SSL isn't forced for them
+fn host_redirects_row(name: &str, host: &HostRedirects) -> [Cell; 5] {
+    let http_redirected = Cell::new(format!(
+        "{} ({:.2}%)",
+        host.http_redirects,
+        calculate_percent(host.http_redirects, host.http_requests)
+    ));
+    [
+        Cell::new(name),
+        Cell::new(host.requests),
+        Cell::new(format!(
+            "{} ({:.2}%)",
+            host.redirects,
+            calculate_percent(host.redirects, host.requests)
+        )),
+        Cell::new(host.http_requests),
+        if host.http_redirects < host.http_requests {
+            http_redirected.fg(Color::DarkRed)
         } else {
-            format!("{parameter_name}s")
+            http_redirected.fg(Color::DarkGreen)
+        },
+    ]
+}
+
+/// Prints client requests in chronological order with pauses marked and client summaries
+pub fn print_client(timeline: &ClientTimeline, requests: bool, limit: usize) {
+    let (Some(first_seen), Some(last_seen)) = (timeline.first_seen, timeline.last_seen) else {
+        println!("No requests of {} found", timeline.client);
+        return;
+    };
+    if requests {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_HORIZONTAL_ONLY)
+            .set_header([
+                Cell::new("#").add_attribute(Attribute::Bold),
+                Cell::new("Time").add_attribute(Attribute::Bold),
+                Cell::new("Host").add_attribute(Attribute::Bold),
+                Cell::new("Status").add_attribute(Attribute::Bold),
+                Cell::new("Method").add_attribute(Attribute::Bold),
+                Cell::new("Schema").add_attribute(Attribute::Bold),
+                Cell::new("Length").add_attribute(Attribute::Bold),
+                Cell::new("Request").add_attribute(Attribute::Bold),
+                Cell::new("Referrer").add_attribute(Attribute::Bold),
+            ])
+            .set_content_arrangement(ContentArrangement::Dynamic);
+        for event in &timeline.events {
+            match event {
+                ClientEvent::Request(entry) => table.add_row([
+                    Cell::new(entry.line),
+                    Cell::new(entry.timestamp),
+                    Cell::new(&entry.host),
+                    colorize_status(Cell::new(entry.status), entry.status),
+                    Cell::new(&entry.method),
+                    Cell::new(&entry.schema),
+                    Cell::new(entry.length),
+                    Cell::new(&entry.request),
+                    Cell::new(&entry.referrer),
+                ]),
+                ClientEvent::Gap(pause) => table.add_row([
+                    Cell::new("···").fg(Color::DarkGrey),
+                    Cell::new(format!("{} without requests", format_duration(*pause)))
+                        .fg(Color::DarkGrey),
+                ]),
+            };
+        }
+        println!("{table}");
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Metric").add_attribute(Attribute::Bold),
+            Cell::new("Value").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    table.add_row([Cell::new("Client IP"), Cell::new(&timeline.client)]);
+    table.add_row([Cell::new("Requests"), Cell::new(timeline.requests)]);
+    table.add_row([Cell::new("Traffic"), Cell::new(HumanBytes(timeline.bytes))]);
+    table.add_row([Cell::new("First seen"), Cell::new(first_seen)]);
+    table.add_row([Cell::new("Last seen"), Cell::new(last_seen)]);
+    table.add_row([
+        Cell::new("Active for"),
+        Cell::new(format_duration(last_seen - first_seen)),
+    ]);
+    println!("{table}");
+
+    let mut hosts: Vec<_> = timeline.hosts.iter().collect();
+    hosts.sort_unstable_by(|a, b| b.1.0.cmp(&a.1.0).then_with(|| a.0.cmp(b.0)));
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Host").add_attribute(Attribute::Bold),
+            Cell::new("Count").add_attribute(Attribute::Bold),
+            Cell::new("Proportion").add_attribute(Attribute::Bold),
+            Cell::new("Traffic").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    for (host, (count, bytes)) in hosts.into_iter().take(limit) {
+        table.add_row([
+            Cell::new(host),
+            Cell::new(count),
+            Cell::new(format!(
+                "{:.2}%",
+                calculate_percent(*count, timeline.requests)
+            )),
+            Cell::new(HumanBytes(*bytes)),
+        ]);
+    }
+    println!("{table}");
+
+    print_counts("Status", &timeline.statuses, limit);
+    print_counts("Agent", &timeline.agents, limit);
+}
+
+/// Prints unique visitors of each period split into new and returning ones
+pub fn print_visitors(summary: &VisitorsSummary) {
+    if summary.periods.is_empty() {
+        return;
+    }
+    let max = summary
+        .periods
+        .iter()
+        .map(|p| p.visitors)
+        .max()
+        .unwrap_or_default();
+    let used =
+        10 + max.to_string().len().max(9) * 3 + summary.requests.to_string().len().max(8) + 14;
+    let chart_width = terminal_width().saturating_sub(used).max(10);
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Period").add_attribute(Attribute::Bold),
+            Cell::new("Visitors").add_attribute(Attribute::Bold),
+            Cell::new("New").add_attribute(Attribute::Bold),
+            Cell::new("Returning").add_attribute(Attribute::Bold),
+            Cell::new("Requests").add_attribute(Attribute::Bold),
+            Cell::new("Chart").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Disabled);
+    for period in &summary.periods {
+        table.add_row([
+            Cell::new(period.start),
+            Cell::new(period.visitors),
+            Cell::new(period.new).fg(Color::DarkGreen),
+            Cell::new(period.returning).fg(Color::DarkCyan),
+            Cell::new(period.requests),
+            Cell::new(stacked_bar(
+                &[period.new, period.returning],
+                max,
+                chart_width,
+            )),
+        ]);
+    }
+    println!("{table}");
+    println!(
+        "Legend: {} new  {} returning",
+        STACK_GLYPHS[0], STACK_GLYPHS[1]
+    );
+    println!("Total unique visitors: {}", summary.visitors);
+    if summary.bot_requests > 0 {
+        println!("Bot requests excluded: {}", summary.bot_requests);
+    }
+}
+
+/// Prints day of week by hour of day grid shaded by metric value
+pub fn print_heatmap(heatmap: &Heatmap) {
+    let total = heatmap.total();
+    if total == 0 {
+        return;
+    }
+    let format = |value: u64| match heatmap.metric() {
+        HeatmapMetric::Bytes => HumanBytes(value).to_string(),
+        HeatmapMetric::Requests | HeatmapMetric::Errors => value.to_string(),
+    };
+    let max = heatmap.max();
+
+    let mut header = vec![Cell::new("Day").add_attribute(Attribute::Bold)];
+    header.extend((0..HOURS).map(|h| Cell::new(format!("{h:02}")).add_attribute(Attribute::Bold)));
+    header.push(Cell::new("Total").add_attribute(Attribute::Bold));
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header(header)
+        .set_content_arrangement(ContentArrangement::Disabled);
+
+    let mut peak = (0, 0);
+    for (day, hours) in heatmap.cells().iter().enumerate() {
+        let mut row = vec![Cell::new(WEEKDAYS[day])];
+        for (hour, value) in hours.iter().enumerate() {
+            if *value == max {
+                peak = (day, hour);
+            }
+            let level = heat_level(*value, max);
+            row.push(Cell::new(HEAT_GLYPHS[level].to_string().repeat(2)).fg(HEAT_COLORS[level]));
+        }
+        row.push(Cell::new(format(hours.iter().sum())));
+        table.add_row(row);
+    }
+    println!("{table}");
+
+    println!("Legend: {}", heat_legend(max, format));
+    println!(
+        "Peak: {} {:02}:00 ({})",
+        WEEKDAYS[peak.0],
+        peak.1,
+        format(max)
+    );
+    println!("Total: {}", format(total));
+}
+
+/// Prints weeks by weekdays calendar shaded by daily requests or error rate.
+/// Calendar is split into several blocks if it doesn't fit terminal width
+pub fn print_calendar(calendar: &Calendar, metric: CalendarMetric) {
+    let weeks = calendar.weeks();
+    if weeks.is_empty() {
+        return;
+    }
+    let format = |value: u64| match metric {
+        CalendarMetric::Requests => value.to_string(),
+        CalendarMetric::ErrorRate => format!("{}.{:02}%", value / 100, value % 100),
+    };
+    let (busiest, max) = calendar
+        .days()
+        .map(|(date, day)| (*date, day.value(metric)))
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .unwrap_or_default();
+
+    // weekday label and two characters for each week
+    let label_width = WEEKDAYS[0].len() + 1;
+    let per_line = (terminal_width().saturating_sub(label_width) / 2).max(1);
+    for (block, chunk) in weeks.chunks(per_line).enumerate() {
+        if block > 0 {
+            println!();
+        }
+        let mut months = " ".repeat(label_width);
+        for (i, week) in chunk.iter().enumerate() {
+            let month_start = (0..7)
+                .map(|d| *week + TimeDelta::days(d))
+                .find(|d| d.day() == 1 && calendar.day(*d).is_some());
+            let label = match month_start {
+                Some(date) if date.month() == 1 => Some(date.format("%b %Y")),
+                Some(date) => Some(date.format("%b")),
+                None if i == 0 => Some(week.format("%b")),
+                None => None,
+            };
+            let column = label_width + i * 2;
+            if let Some(label) = label
+                && months.chars().count() <= column
+            {
+                months.push_str(&" ".repeat(column - months.chars().count()));
+                months.push_str(&label.to_string());
+                months.push(' ');
+            }
+        }
+        println!("{}", months.trim_end());
+
+        for (weekday, name) in (0..).zip(WEEKDAYS) {
+            let mut line = format!("{name:<label_width$}");
+            for week in chunk {
+                let date = *week + TimeDelta::days(weekday);
+                line.push(match calendar.day(date) {
+                    Some(day) if day.requests == 0 => NO_REQUESTS_GLYPH,
+                    Some(day) => match heat_level(day.value(metric), max) {
+                        0 => ZERO_GLYPH,
+                        level => HEAT_GLYPHS[level],
+                    },
+                    None => ' ',
+                });
+                line.push(' ');
+            }
+            println!("{}", line.trim_end());
+        }
+    }
+
+    println!(
+        "Legend: {NO_REQUESTS_GLYPH} no requests  {ZERO_GLYPH} {}  {}",
+        format(0),
+        heat_legend(max, format)
+    );
+    let title = match metric {
+        CalendarMetric::Requests => "Busiest day",
+        CalendarMetric::ErrorRate => "Worst day",
+    };
+    println!("{title}: {busiest} ({})", format(max));
+    if let Some((first, last)) = calendar.period() {
+        println!("Period: {first} - {last}");
+    }
+}
+
+/// Prints intervals without log entries or with 5xx responses only
+pub fn print_outages(outages: &[Outage]) {
+    if outages.is_empty() {
+        println!("No outages found");
+        return;
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header([
+            Cell::new("Start").add_attribute(Attribute::Bold),
+            Cell::new("End").add_attribute(Attribute::Bold),
+            Cell::new("Duration").add_attribute(Attribute::Bold),
+            Cell::new("Scope").add_attribute(Attribute::Bold),
+            Cell::new("Kind").add_attribute(Attribute::Bold),
+            Cell::new("5xx").add_attribute(Attribute::Bold),
+            Cell::new("Expected requests").add_attribute(Attribute::Bold),
+        ])
+        .set_content_arrangement(ContentArrangement::Dynamic);
+
+    for outage in outages {
+        let kind = Cell::new(outage.kind);
+        let kind = match outage.kind {
+            OutageKind::NoEntries => kind.fg(Color::DarkYellow),
+            OutageKind::ServerErrors => kind.fg(Color::DarkRed),
         };
-        let spacer = " ".repeat(group.len() - 4); // 4 is data len
-        println!("Total {group}:\t{total}");
-        println!("Total data:{spacer}\t{total_count}");
+        table.add_row([
+            Cell::new(outage.start.format("%Y-%m-%d %H:%M")),
+            Cell::new(outage.end.format("%Y-%m-%d %H:%M")),
+            Cell::new(format_duration(outage.duration())),
+            Cell::new(&outage.scope),
+            kind,
+            Cell::new(outage.errors),
+            Cell::new(format!("{:.0}", outage.expected)),
+        ]);
+    }
+    println!("{table}");
+    let missing = outages
+        .iter()
+        .filter(|o| o.scope == OutageScope::All && o.kind == OutageKind::NoEntries)
+        .count();
+    if missing > 0 {
+        println!("Intervals without entries of all hosts: {missing} (logs may be missing)");
     }
+    println!("Total outages: {}", outages.len());
 }
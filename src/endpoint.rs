@@ -0,0 +1,173 @@
+use std::borrow::Cow;
+use std::sync::LazyLock;
+
+use color_eyre::eyre::{Result, eyre};
+use regex::Regex;
+
+use crate::request_path;
+
+/// Built-in path segment rules applied after custom ones. The first matching rule wins
+static BUILTIN_RULES: LazyLock<[(Regex, &str); 4]> = LazyLock::new(|| {
+    [
+        (
+            Regex::new(
+                r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$",
+            )
+            .expect("valid regex"),
+            "{uuid}",
+        ),
+        (
+            Regex::new(r"^\d{4}-\d{2}-\d{2}$").expect("valid regex"),
+            "{date}",
+        ),
+        (Regex::new(r"^\d+$").expect("valid regex"), "{id}"),
+        (
+            Regex::new(r"^[0-9a-fA-F]{16,}$").expect("valid regex"),
+            "{hash}",
+        ),
+    ]
+});
+
+/// Rewrite rule that replaces regex matches within request path
+#[derive(Debug)]
+pub struct RewriteRule {
+    regex: Regex,
+    replacement: String,
+}
+
+/// Turns request into endpoint by replacing variable parts of the path with placeholders
+#[derive(Debug, Default)]
+pub struct Normalizer {
+    rules: Vec<RewriteRule>,
+    strip_query: bool,
+}
+
+/// Parses rewrite rules one per line. Each rule is regex and replacement separated by whitespace.
+/// Replacement can refer capture groups like `$1`. Empty lines and lines started from `#` are ignored
+///
+/// # Errors
+///
+/// This function will return an error if any rule has no replacement or invalid regular expression
+pub fn parse_rewrite_rules(text: &str) -> Result<Vec<RewriteRule>> {
+    let mut rules = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((pattern, replacement)) = line.split_once(char::is_whitespace) else {
+            return Err(eyre!(
+                "Rule at line {} must have regex and replacement",
+                number + 1
+            ));
+        };
+        let regex =
+            Regex::new(pattern).map_err(|e| eyre!("Invalid regex at line {}: {e}", number + 1))?;
+        rules.push(RewriteRule {
+            regex,
+            replacement: replacement.trim().to_owned(),
+        });
+    }
+    Ok(rules)
+}
+
+impl Normalizer {
+    #[must_use]
+    pub fn new(rules: Vec<RewriteRule>, strip_query: bool) -> Self {
+        Self { rules, strip_query }
+    }
+
+    /// Applies custom rules to the path and then replaces numeric IDs, UUIDs, dates
+    /// and hex hashes path segments with `{id}`, `{uuid}`, `{date}` and `{hash}`
+    #[must_use]
+    pub fn normalize(&self, request: &str) -> String {
+        let path = request_path(request);
+        let query = &request[path.len()..];
+        let mut path = path.to_owned();
+        for rule in &self.rules {
+            if let Cow::Owned(replaced) = rule.regex.replace_all(&path, rule.replacement.as_str()) {
+                path = replaced;
+            }
+        }
+        let mut result: String = path
+            .split('/')
+            .map(|segment| {
+                BUILTIN_RULES
+                    .iter()
+                    .find(|(regex, _)| regex.is_match(segment))
+                    .map_or(segment, |(_, placeholder)| placeholder)
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        if !self.strip_query {
+            result.push_str(query);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("/api/users/123", "/api/users/{id}")]
+    #[test_case("/api/users/123/orders/45", "/api/users/{id}/orders/{id}")]
+    #[test_case("/files/550e8400-e29b-41d4-a716-446655440000", "/files/{uuid}")]
+    #[test_case("/static/app.3f2a9c1b7d4e8f60.js", "/static/app.3f2a9c1b7d4e8f60.js")]
+    #[test_case("/blobs/3f2a9c1b7d4e8f60a1b2", "/blobs/{hash}")]
+    #[test_case("/reports/2024-05-01", "/reports/{date}")]
+    #[test_case("/api/v2/items?id=5", "/api/v2/items?id=5")]
+    #[test_case("/", "/")]
+    #[test_case("", "")]
+    fn builtin_rules_tests(request: &str, expected: &str) {
+        // Arrange
+        let normalizer = Normalizer::default();
+
+        // Act
+        let actual = normalizer.normalize(request);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn query_stripped() {
+        // Arrange
+        let normalizer = Normalizer::new(vec![], true);
+
+        // Act
+        let actual = normalizer.normalize("/api/users/5?expand=orders");
+
+        // Assert
+        assert_eq!(actual, "/api/users/{id}");
+    }
+
+    #[test]
+    fn custom_rules_applied_before_builtin() {
+        // Arrange
+        let rules =
+            parse_rewrite_rules("# users by name\n^/u/[^/]+ /u/{name}\n\n(\\w+)\\.php$ $1\n")
+                .unwrap();
+        let normalizer = Normalizer::new(rules, false);
+
+        // Act
+        let actual = normalizer.normalize("/u/john/posts/12/index.php");
+
+        // Assert
+        assert_eq!(actual, "/u/{name}/posts/{id}/index");
+    }
+
+    #[test_case("^/u/")]
+    #[test_case("( x")]
+    fn invalid_rules(text: &str) {
+        // Arrange
+
+        // Act
+        let actual = parse_rewrite_rules(text);
+
+        // Assert
+        assert!(actual.is_err());
+    }
+}
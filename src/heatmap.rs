@@ -0,0 +1,233 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, FixedOffset, TimeZone, Timelike};
+use chrono_tz::Tz;
+use clap::{ValueEnum, builder::PossibleValue};
+
+use crate::LogEntry;
+
+/// Days of week in the order of heatmap rows
+pub const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+pub const HOURS: usize = 24;
+
+/// Value summed in heatmap cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeatmapMetric {
+    Requests,
+    Bytes,
+    /// Requests answered with 4xx or 5xx
+    Errors,
+}
+
+impl ValueEnum for HeatmapMetric {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            HeatmapMetric::Requests,
+            HeatmapMetric::Bytes,
+            HeatmapMetric::Errors,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            HeatmapMetric::Requests => PossibleValue::new("requests"),
+            HeatmapMetric::Bytes => PossibleValue::new("bytes"),
+            HeatmapMetric::Errors => PossibleValue::new("errors"),
+        })
+    }
+}
+
+/// Time zone to convert timestamps into. Either fixed UTC offset or IANA zone with daylight saving time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+/// Parses UTC offset like `+03:00` or `-0530` and IANA zone name like `Europe/Berlin` or `UTC`
+impl FromStr for Zone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(offset) = s.parse() {
+            return Ok(Zone::Fixed(offset));
+        }
+        s.parse().map(Zone::Named).map_err(|_| {
+            format!("'{s}' is neither UTC offset like +03:00 nor time zone like Europe/Berlin")
+        })
+    }
+}
+
+/// Day of week (Monday is zero) and hour of timestamp in time zone
+fn weekday_and_hour<T: TimeZone>(time: &DateTime<FixedOffset>, zone: &T) -> (usize, usize) {
+    let time = time.with_timezone(zone);
+    (
+        time.weekday().num_days_from_monday() as usize,
+        time.hour() as usize,
+    )
+}
+
+/// Metric values by day of week (Monday first) and hour of day
+#[derive(Debug)]
+pub struct Heatmap {
+    metric: HeatmapMetric,
+    zone: Option<Zone>,
+    cells: [[u64; HOURS]; 7],
+}
+
+impl Heatmap {
+    /// Creates heatmap. Timestamps are converted into `zone` if it's set and are used as is otherwise
+    #[must_use]
+    pub fn new(metric: HeatmapMetric, zone: Option<Zone>) -> Self {
+        Self {
+            metric,
+            zone,
+            cells: [[0; HOURS]; 7],
+        }
+    }
+
+    /// Adds entry into its cell. Entries without timestamp are ignored
+    pub fn add(&mut self, entry: &LogEntry) {
+        if !entry.has_timestamp() {
+            return;
+        }
+        let value = match self.metric {
+            HeatmapMetric::Requests => 1,
+            HeatmapMetric::Bytes => entry.length,
+            HeatmapMetric::Errors => u64::from(entry.status >= 400),
+        };
+        let (day, hour) = match &self.zone {
+            Some(Zone::Fixed(offset)) => weekday_and_hour(&entry.timestamp, offset),
+            Some(Zone::Named(tz)) => weekday_and_hour(&entry.timestamp, tz),
+            None => weekday_and_hour(&entry.timestamp, entry.timestamp.offset()),
+        };
+        self.cells[day][hour] += value;
+    }
+
+    #[must_use]
+    pub fn metric(&self) -> HeatmapMetric {
+        self.metric
+    }
+
+    #[must_use]
+    pub fn cells(&self) -> &[[u64; HOURS]; 7] {
+        &self.cells
+    }
+
+    /// The biggest cell value
+    #[must_use]
+    pub fn max(&self) -> u64 {
+        self.cells
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.cells.iter().flatten().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(timestamp: &str, status: u16, length: u64) -> LogEntry {
+        LogEntryBuilder::new()
+            .timestamp(timestamp)
+            .status(status)
+            .length(length)
+            .build()
+    }
+
+    fn fill(heatmap: &mut Heatmap) {
+        // Wednesday
+        heatmap.add(&entry("2024-05-01T10:15:00+03:00", 200, 100));
+        heatmap.add(&entry("2024-05-01T10:45:00+03:00", 500, 200));
+        // Sunday
+        heatmap.add(&entry("2024-05-05T23:30:00+03:00", 404, 300));
+        heatmap.add(&LogEntry::default());
+    }
+
+    #[test]
+    fn requests_by_weekday_and_hour() {
+        // Arrange
+        let mut heatmap = Heatmap::new(HeatmapMetric::Requests, None);
+
+        // Act
+        fill(&mut heatmap);
+
+        // Assert
+        assert_eq!(heatmap.cells()[2][10], 2);
+        assert_eq!(heatmap.cells()[6][23], 1);
+        assert_eq!(heatmap.max(), 2);
+        assert_eq!(heatmap.total(), 3);
+    }
+
+    #[test]
+    fn bytes_and_errors_summed() {
+        // Arrange
+        let mut bytes = Heatmap::new(HeatmapMetric::Bytes, None);
+        let mut errors = Heatmap::new(HeatmapMetric::Errors, None);
+
+        // Act
+        fill(&mut bytes);
+        fill(&mut errors);
+
+        // Assert
+        assert_eq!(bytes.cells()[2][10], 300);
+        assert_eq!(errors.cells()[2][10], 1);
+        assert_eq!(errors.cells()[6][23], 1);
+    }
+
+    #[test]
+    fn converted_into_time_zone() {
+        // Arrange
+        let mut heatmap = Heatmap::new(HeatmapMetric::Requests, "-02:00".parse().ok());
+
+        // Act
+        fill(&mut heatmap);
+
+        // Assert
+        assert_eq!(heatmap.cells()[2][5], 2);
+        assert_eq!(heatmap.cells()[6][18], 1);
+    }
+
+    #[test_case("UTC", "2024-07-15T12:00:00Z", 0, 12)]
+    #[test_case("+03:00", "2024-07-15T12:00:00Z", 0, 15)]
+    #[test_case("-0530", "2024-07-15T12:00:00Z", 0, 6)]
+    #[test_case("Europe/Berlin", "2024-01-15T12:00:00Z", 0, 13)]
+    #[test_case("Europe/Berlin", "2024-07-15T12:00:00Z", 0, 14)]
+    #[test_case("America/New_York", "2024-07-15T02:00:00Z", 6, 22)]
+    #[test_case("Australia/Sydney", "2024-10-06T16:30:00Z", 0, 3)]
+    fn zone_tests(zone: &str, time: &str, day: usize, hour: usize) {
+        // Arrange
+        let mut heatmap = Heatmap::new(HeatmapMetric::Requests, Some(zone.parse().unwrap()));
+
+        // Act
+        heatmap.add(&entry(time, 200, 0));
+
+        // Assert
+        assert_eq!(heatmap.cells()[day][hour], 1);
+    }
+
+    #[test_case("Europe/Nowhere")]
+    #[test_case("03:00")]
+    #[test_case("+3")]
+    #[test_case("+03:75")]
+    fn zone_invalid_tests(value: &str) {
+        // Arrange
+
+        // Act
+        let actual = value.parse::<Zone>();
+
+        // Assert
+        assert!(actual.is_err());
+    }
+}
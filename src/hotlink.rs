@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use crate::domain::{ReferrerType, referrer_domain, referrer_type};
+use crate::{LogEntry, request_path};
+
+/// Extensions of images, video and archives checked when none are configured
+pub const DEFAULT_ASSET_EXTENSIONS: [&str; 27] = [
+    "jpg", "jpeg", "png", "gif", "webp", "avif", "svg", "ico", "bmp", "tif", "tiff", "mp4", "webm",
+    "mkv", "avi", "mov", "m4v", "ogv", "flv", "zip", "rar", "7z", "tar", "gz", "tgz", "bz2", "xz",
+];
+
+/// Referring site that embeds or links our static assets
+#[derive(Debug)]
+pub struct HotlinkingDomain {
+    pub domain: String,
+    pub requests: u64,
+    pub bytes: u64,
+    /// Distinct assets requested
+    pub assets: usize,
+    /// Asset that served the most bytes to this domain
+    pub top_asset: String,
+    pub top_asset_bytes: u64,
+    /// Our hosts serving the assets
+    pub hosts: Vec<String>,
+}
+
+/// Traffic totals and hotlinking domains ordered by bytes served
+#[derive(Debug, Default)]
+pub struct HotlinkSummary {
+    pub total_bytes: u64,
+    /// Bytes of static assets requested with any referrer or without it
+    pub asset_bytes: u64,
+    /// Bytes of static assets requested with external referrer
+    pub hotlinked_bytes: u64,
+    pub domains: Vec<HotlinkingDomain>,
+}
+
+#[derive(Debug, Default)]
+struct DomainStats {
+    requests: u64,
+    bytes: u64,
+    assets: HashMap<String, u64>,
+    hosts: HashMap<String, u64>,
+}
+
+/// Sums bytes of static assets served to pages of other sites by referring domain
+#[derive(Debug)]
+pub struct HotlinkDetector {
+    extensions: Vec<String>,
+    total_bytes: u64,
+    asset_bytes: u64,
+    domains: HashMap<String, DomainStats>,
+}
+
+impl HotlinkDetector {
+    /// Creates detector of assets with extensions specified. Extensions are compared case insensitive
+    #[must_use]
+    pub fn new(extensions: &[String]) -> Self {
+        Self {
+            extensions: extensions
+                .iter()
+                .map(|e| e.trim_start_matches('.').to_lowercase())
+                .collect(),
+            total_bytes: 0,
+            asset_bytes: 0,
+            domains: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, entry: &LogEntry) {
+        self.total_bytes += entry.length;
+        let path = request_path(&entry.request);
+        if !self.is_asset(path) {
+            return;
+        }
+        self.asset_bytes += entry.length;
+        if referrer_type(&entry.referrer, &entry.host) != ReferrerType::External {
+            return;
+        }
+        let stats = self
+            .domains
+            .entry(referrer_domain(&entry.referrer))
+            .or_default();
+        stats.requests += 1;
+        stats.bytes += entry.length;
+        *stats.assets.entry(path.to_owned()).or_default() += entry.length;
+        *stats.hosts.entry(entry.host.clone()).or_default() += entry.length;
+    }
+
+    #[must_use]
+    pub fn finish(self) -> HotlinkSummary {
+        let mut domains: Vec<_> = self
+            .domains
+            .into_iter()
+            .map(|(domain, stats)| {
+                let (top_asset, top_asset_bytes) = stats
+                    .assets
+                    .iter()
+                    .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                    .map(|(asset, bytes)| (asset.clone(), *bytes))
+                    .unwrap_or_default();
+                let mut hosts: Vec<_> = stats.hosts.into_iter().collect();
+                hosts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                HotlinkingDomain {
+                    domain,
+                    requests: stats.requests,
+                    bytes: stats.bytes,
+                    assets: stats.assets.len(),
+                    top_asset,
+                    top_asset_bytes,
+                    hosts: hosts.into_iter().map(|(host, _)| host).collect(),
+                }
+            })
+            .collect();
+        domains
+            .sort_unstable_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.domain.cmp(&b.domain)));
+        HotlinkSummary {
+            total_bytes: self.total_bytes,
+            asset_bytes: self.asset_bytes,
+            hotlinked_bytes: domains.iter().map(|d| d.bytes).sum(),
+            domains,
+        }
+    }
+
+    fn is_asset(&self, path: &str) -> bool {
+        let file = path.rsplit('/').next().unwrap_or_default();
+        file.rsplit_once('.').is_some_and(|(_, extension)| {
+            self.extensions
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(extension))
+        })
+    }
+}
+
+impl Default for HotlinkDetector {
+    fn default() -> Self {
+        Self::new(&DEFAULT_ASSET_EXTENSIONS.map(String::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(request: &str, referrer: &str, length: u64) -> LogEntry {
+        LogEntryBuilder::new()
+            .host("example.com")
+            .request(request)
+            .referrer(referrer)
+            .length(length)
+            .build()
+    }
+
+    #[test_case("/img/logo.PNG", true)]
+    #[test_case("/video/intro.mp4?t=10", true)]
+    #[test_case("/downloads/app.tar.gz", true)]
+    #[test_case("/index.html", false)]
+    #[test_case("/img.png/view", false)]
+    #[test_case("/", false)]
+    fn asset_tests(request: &str, expected: bool) {
+        // Arrange
+        let mut detector = HotlinkDetector::default();
+
+        // Act
+        detector.add(&entry(request, "https://forum.other.org/thread", 10));
+
+        // Assert
+        assert_eq!(detector.finish().hotlinked_bytes > 0, expected);
+    }
+
+    #[test]
+    fn hotlinking_domains_ranked_by_bytes() {
+        // Arrange
+        let mut detector = HotlinkDetector::default();
+
+        // Act
+        detector.add(&entry("/a.jpg", "https://forum.other.org/t/1", 100));
+        detector.add(&entry("/b.jpg", "https://www.other.org/", 300));
+        detector.add(&entry("/b.jpg", "https://www.other.org/", 300));
+        detector.add(&entry("/video.mp4", "https://blog.spam.co.uk/", 500));
+        detector.add(&entry("/a.jpg", "https://www.example.com/page", 1000));
+        detector.add(&entry("/a.jpg", "-", 2000));
+        detector.add(&entry("/page", "https://www.other.org/", 4000));
+
+        // Assert
+        let actual = detector.finish();
+        assert_eq!(actual.total_bytes, 8200);
+        assert_eq!(actual.asset_bytes, 4200);
+        assert_eq!(actual.hotlinked_bytes, 1200);
+        let domains: Vec<_> = actual
+            .domains
+            .iter()
+            .map(|d| (d.domain.as_str(), d.requests, d.bytes, d.assets))
+            .collect();
+        assert_eq!(
+            domains,
+            vec![("other.org", 3, 700, 2), ("spam.co.uk", 1, 500, 1)]
+        );
+        assert_eq!(actual.domains[0].top_asset, "/b.jpg");
+        assert_eq!(actual.domains[0].top_asset_bytes, 600);
+        assert_eq!(actual.domains[0].hosts, vec!["example.com"]);
+    }
+
+    #[test]
+    fn custom_extensions_used() {
+        // Arrange
+        let mut detector = HotlinkDetector::new(&[".PDF".to_owned()]);
+
+        // Act
+        detector.add(&entry("/doc.pdf", "https://other.org/", 10));
+        detector.add(&entry("/a.jpg", "https://other.org/", 20));
+
+        // Assert
+        assert_eq!(detector.finish().hotlinked_bytes, 10);
+    }
+}
@@ -1,19 +1,45 @@
-use chrono::Datelike;
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeDelta};
 use clap::ValueEnum;
-use clap::builder::PossibleValue;
+use clap::builder::{PossibleValue, TypedValueParser};
 
 use core::hash::Hash;
 use std::borrow::Cow;
+use std::cell::RefCell;
 use std::fmt::Display;
 
 use async_stream::stream;
+use endpoint::Normalizer;
 use filter::Criteria;
+use percent_encoding::percent_decode_str;
+use template::TemplateMiner;
 use tokio_stream::{Stream, StreamExt};
 
+pub mod anomaly;
+pub mod blocklist;
+pub mod brokenlink;
+pub mod bruteforce;
+pub mod calendar;
+pub mod chart;
+pub mod client;
 pub mod console;
+pub mod domain;
+pub mod endpoint;
 pub mod filter;
+pub mod heatmap;
+pub mod hotlink;
 mod io;
+pub mod outage;
+pub mod pivot;
+pub mod query;
+pub mod ratelimit;
+pub mod redirect;
+pub mod scanner;
+pub mod session;
+pub mod signature;
+pub mod sketch;
+pub mod template;
+pub mod timeline;
+pub mod visitors;
 
 pub use io::read_strings_from_file;
 pub use io::read_strings_from_stdin;
@@ -73,6 +99,8 @@ pub fn convert<'a, S>(
     input: S,
     filter: &'a Criteria,
     parameter: Option<LogParameter>,
+    normalizer: Option<&'a Normalizer>,
+    miner: Option<&'a RefCell<TemplateMiner>>,
 ) -> impl Stream<Item = LogEntry> + 'a
 where
     S: Stream<Item = String> + 'a,
@@ -86,8 +114,14 @@ where
             }
 
             if let Ok(jsonl_entry) = serde_json::from_str::<JsonlEntry>(&line) {
-                let entry = LogEntry::from_jsonl(jsonl_entry);
-                if entry.allow(filter, parameter) {
+                let mut entry = LogEntry::from_jsonl(jsonl_entry);
+                if let Some(normalizer) = normalizer {
+                    entry.endpoint = normalizer.normalize(&entry.request);
+                }
+                if let Some(miner) = miner {
+                    entry.template = miner.borrow_mut().add(&entry.request);
+                }
+                if entry.allow(filter, parameter.as_ref()) {
                     yield entry;
                 }
             }
@@ -105,7 +139,75 @@ pub fn calculate_percent(value: u64, total: u64) -> f64 {
     }
 }
 
-#[derive(Default, Debug)]
+/// Parses duration like `30s`, `15m`, `1h`, `1d` or `1w`. Number without suffix means seconds
+///
+/// # Errors
+///
+/// This function will return an error if duration is not a positive number with optional known suffix
+pub fn parse_duration(value: &str) -> Result<TimeDelta, String> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(ix) => value.split_at(ix),
+        None => (value, "s"),
+    };
+    let number: i64 = number
+        .parse()
+        .map_err(|_| format!("Invalid duration '{value}'"))?;
+    let duration = match unit {
+        "s" => TimeDelta::try_seconds(number),
+        "m" => TimeDelta::try_minutes(number),
+        "h" => TimeDelta::try_hours(number),
+        "d" => TimeDelta::try_days(number),
+        "w" => TimeDelta::try_weeks(number),
+        _ => None,
+    };
+    duration.filter(|d| *d > TimeDelta::zero()).ok_or_else(|| {
+        format!("Invalid duration '{value}'. Use number with s, m, h, d or w suffix")
+    })
+}
+
+/// Returns request path without query string
+#[must_use]
+pub fn request_path(request: &str) -> &str {
+    request.split_once('?').map_or(request, |(path, _)| path)
+}
+
+/// Percent-decodes form encoded value where plus means space. Value is decoded
+/// up to `passes` times to reveal double encoded payloads
+#[must_use]
+pub fn percent_decode(value: &str, passes: usize) -> Cow<'_, str> {
+    let mut result = Cow::Borrowed(value);
+    for _ in 0..passes {
+        if !result.contains(['%', '+']) {
+            break;
+        }
+        let decoded = percent_decode_str(&result.replace('+', " "))
+            .decode_utf8_lossy()
+            .into_owned();
+        result = Cow::Owned(decoded);
+    }
+    result
+}
+
+/// All HTTP status classes. Status that cannot be parsed is `invalid`
+pub const STATUS_CLASSES: [&str; 6] = ["1xx", "2xx", "3xx", "4xx", "5xx", "invalid"];
+
+/// Returns index of HTTP status class in `STATUS_CLASSES`
+#[must_use]
+pub fn status_class_index(status: u16) -> usize {
+    match status {
+        100..=599 => usize::from(status / 100 - 1),
+        _ => STATUS_CLASSES.len() - 1,
+    }
+}
+
+/// Returns HTTP status class like `2xx`. Status that cannot be parsed is `invalid`
+#[must_use]
+pub fn status_class(status: u16) -> &'static str {
+    STATUS_CLASSES[status_class_index(status)]
+}
+
+#[derive(Default, Debug, Clone)]
 pub struct LogEntry {
     pub agent: String,
     pub clientip: String,
@@ -114,6 +216,10 @@ pub struct LogEntry {
     pub length: u64,
     pub method: String,
     pub request: String,
+    /// Request with variable path parts replaced by placeholders
+    pub endpoint: String,
+    /// URL template mined from request paths
+    pub template: String,
     pub referrer: String,
     pub schema: String,
     pub serverhost: String,
@@ -143,6 +249,8 @@ impl LogEntry {
             length,
             method: props.method,
             request: props.request,
+            endpoint: String::new(),
+            template: String::new(),
             referrer: props.referrer,
             schema: props.schema,
             serverhost: props.serverhost,
@@ -152,23 +260,128 @@ impl LogEntry {
         }
     }
 
-    fn allow(&self, filter: &Criteria, parameter: Option<LogParameter>) -> bool {
+    /// Whether timestamp was parsed successfully
+    #[must_use]
+    pub fn has_timestamp(&self) -> bool {
+        self.timestamp != DateTime::<FixedOffset>::default()
+    }
+
+    /// Date in log time zone. It's the value of `date` parameter
+    #[must_use]
+    pub fn date(&self) -> NaiveDate {
+        self.timestamp.date_naive()
+    }
+
+    fn allow(&self, filter: &Criteria, parameter: Option<&LogParameter>) -> bool {
         parameter.is_none_or(|p| filter.allow(&p.extract(self)))
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Default)]
+/// Builds log entries in tests. Entries have `2024-05-01T00:00:00Z` timestamp unless it's changed
+#[cfg(test)]
+pub(crate) struct LogEntryBuilder(LogEntry);
+
+#[cfg(test)]
+impl LogEntryBuilder {
+    pub(crate) fn new() -> Self {
+        Self(LogEntry::default()).timestamp("2024-05-01T00:00:00Z")
+    }
+
+    /// Sets RFC 3339 timestamp
+    pub(crate) fn timestamp(mut self, value: &str) -> Self {
+        self.0.timestamp = DateTime::parse_from_rfc3339(value).unwrap();
+        self
+    }
+
+    /// Moves timestamp forward
+    pub(crate) fn after(mut self, delta: TimeDelta) -> Self {
+        self.0.timestamp += delta;
+        self
+    }
+
+    pub(crate) fn client(mut self, value: &str) -> Self {
+        value.clone_into(&mut self.0.clientip);
+        self
+    }
+
+    pub(crate) fn host(mut self, value: &str) -> Self {
+        value.clone_into(&mut self.0.host);
+        self
+    }
+
+    pub(crate) fn method(mut self, value: &str) -> Self {
+        value.clone_into(&mut self.0.method);
+        self
+    }
+
+    pub(crate) fn request(mut self, value: &str) -> Self {
+        value.clone_into(&mut self.0.request);
+        self
+    }
+
+    pub(crate) fn referrer(mut self, value: &str) -> Self {
+        value.clone_into(&mut self.0.referrer);
+        self
+    }
+
+    pub(crate) fn agent(mut self, value: &str) -> Self {
+        value.clone_into(&mut self.0.agent);
+        self
+    }
+
+    pub(crate) fn schema(mut self, value: &str) -> Self {
+        value.clone_into(&mut self.0.schema);
+        self
+    }
+
+    pub(crate) fn status(mut self, value: u16) -> Self {
+        self.0.status = value;
+        self
+    }
+
+    pub(crate) fn length(mut self, value: u64) -> Self {
+        self.0.length = value;
+        self
+    }
+
+    pub(crate) fn line(mut self, value: u64) -> Self {
+        self.0.line = value;
+        self
+    }
+
+    pub(crate) fn build(self) -> LogEntry {
+        self.0
+    }
+}
+
+/// Parameter of log entry used for grouping and filtering. It's not `Copy` since
+/// `Query` variant owns the query parameter name so clone it where a copy was used before
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Default)]
 pub enum LogParameter {
     Time,
     Agent,
     ClientIp,
     Status,
+    StatusClass,
     Method,
     Schema,
     #[default]
     Request,
+    Endpoint,
+    Template,
+    /// Percent-decoded request path without query string
+    Path,
+    /// Sorted distinct query parameters names
+    QueryKeys,
+    /// Values of query parameter with the name
+    Query(String),
     Referrer,
+    /// Registrable domain of referrer
+    ReferrerDomain,
+    /// Whether referrer is internal, external or none
+    ReferrerType,
     Date,
+    Host,
 }
 
 impl LogParameter {
@@ -180,15 +393,21 @@ impl LogParameter {
             LogParameter::Method => Cow::Borrowed(&entry.method),
             LogParameter::Schema => Cow::Borrowed(&entry.schema),
             LogParameter::Request => Cow::Borrowed(&entry.request),
+            LogParameter::Endpoint => Cow::Borrowed(&entry.endpoint),
+            LogParameter::Template => Cow::Borrowed(&entry.template),
+            LogParameter::Path => query::path(&entry.request),
+            LogParameter::QueryKeys => Cow::Owned(query::keys(&entry.request)),
+            LogParameter::Query(name) => query::value(&entry.request, name),
             LogParameter::Referrer => Cow::Borrowed(&entry.referrer),
+            LogParameter::ReferrerDomain => Cow::Owned(domain::referrer_domain(&entry.referrer)),
+            LogParameter::ReferrerType => {
+                Cow::Borrowed(domain::referrer_type(&entry.referrer, &entry.host).as_str())
+            }
+            LogParameter::Host => Cow::Borrowed(&entry.host),
             LogParameter::Status => Cow::Owned(entry.status.to_string()),
+            LogParameter::StatusClass => Cow::Borrowed(status_class(entry.status)),
             LogParameter::Time => Cow::Owned(entry.timestamp.to_string()),
-            LogParameter::Date => Cow::Owned(format!(
-                "{}-{:02}-{:02}",
-                entry.timestamp.year(),
-                entry.timestamp.month(),
-                entry.timestamp.day()
-            )),
+            LogParameter::Date => Cow::Owned(entry.date().to_string()),
         }
     }
 }
@@ -197,14 +416,61 @@ impl LogParameter {
 pub struct GroupedParameter<T: Display + Hash + Eq> {
     pub parameter: T,
     pub count: u64,
+    /// Estimated number of distinct values of another parameter within the group
+    pub distinct: Option<u64>,
+    /// Maximum overestimation of `count` if it's approximated
+    pub error: Option<u64>,
 }
 
 impl Display for LogParameter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.to_possible_value()
-            .expect("no values are skipped")
-            .get_name()
-            .fmt(f)
+        match self {
+            LogParameter::Query(name) => write!(f, "{QUERY_PREFIX}{name}"),
+            _ => self
+                .to_possible_value()
+                .expect("only query is skipped")
+                .get_name()
+                .fmt(f),
+        }
+    }
+}
+
+/// Prefix of query parameter like `query:utm_source`
+const QUERY_PREFIX: &str = "query:";
+
+/// Parses parameters listed by `ValueEnum` and `query:<name>` ones
+#[derive(Clone, Debug)]
+pub struct LogParameterParser;
+
+impl TypedValueParser for LogParameterParser {
+    type Value = LogParameter;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = value.to_string_lossy();
+        if let Some(name) = value.strip_prefix(QUERY_PREFIX)
+            && !name.is_empty()
+        {
+            return Ok(LogParameter::Query(name.to_owned()));
+        }
+        clap::builder::EnumValueParser::<LogParameter>::new().parse_ref(
+            cmd,
+            arg,
+            std::ffi::OsStr::new(value.as_ref()),
+        )
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            LogParameter::value_variants()
+                .iter()
+                .filter_map(ValueEnum::to_possible_value)
+                .chain(std::iter::once(PossibleValue::new("query:<name>"))),
+        ))
     }
 }
 
@@ -217,10 +483,18 @@ impl ValueEnum for LogParameter {
             LogParameter::Agent,
             LogParameter::ClientIp,
             LogParameter::Status,
+            LogParameter::StatusClass,
             LogParameter::Method,
             LogParameter::Schema,
             LogParameter::Request,
+            LogParameter::Endpoint,
+            LogParameter::Template,
+            LogParameter::Path,
+            LogParameter::QueryKeys,
             LogParameter::Referrer,
+            LogParameter::ReferrerDomain,
+            LogParameter::ReferrerType,
+            LogParameter::Host,
         ]
     }
 
@@ -231,10 +505,19 @@ impl ValueEnum for LogParameter {
             LogParameter::Agent => PossibleValue::new("agent"),
             LogParameter::ClientIp => PossibleValue::new("client"),
             LogParameter::Status => PossibleValue::new("status"),
+            LogParameter::StatusClass => PossibleValue::new("class"),
             LogParameter::Method => PossibleValue::new("method"),
             LogParameter::Schema => PossibleValue::new("schema"),
             LogParameter::Request => PossibleValue::new("req"),
+            LogParameter::Endpoint => PossibleValue::new("endpoint"),
+            LogParameter::Template => PossibleValue::new("template"),
+            LogParameter::Path => PossibleValue::new("path"),
+            LogParameter::QueryKeys => PossibleValue::new("query_keys"),
+            LogParameter::Query(_) => return None,
             LogParameter::Referrer => PossibleValue::new("ref"),
+            LogParameter::ReferrerDomain => PossibleValue::new("ref_domain"),
+            LogParameter::ReferrerType => PossibleValue::new("ref_type"),
+            LogParameter::Host => PossibleValue::new("host"),
         })
     }
 }
@@ -259,4 +542,97 @@ mod tests {
         // Assert
         assert_eq!(actual, expected);
     }
+
+    #[test_case("30", 30)]
+    #[test_case("30s", 30)]
+    #[test_case("15m", 900)]
+    #[test_case("1h", 3600)]
+    #[test_case("2d", 172_800)]
+    #[test_case("1w", 604_800)]
+    fn parse_duration_tests(value: &str, expected: i64) {
+        // Arrange
+
+        // Act
+        let actual = parse_duration(value);
+
+        // Assert
+        assert_eq!(actual, Ok(TimeDelta::seconds(expected)));
+    }
+
+    #[test_case("")]
+    #[test_case("0m")]
+    #[test_case("h")]
+    #[test_case("10y")]
+    #[test_case("-1h")]
+    fn parse_duration_invalid_tests(value: &str) {
+        // Arrange
+
+        // Act
+        let actual = parse_duration(value);
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[test_case(0, "invalid")]
+    #[test_case(101, "1xx")]
+    #[test_case(200, "2xx")]
+    #[test_case(304, "3xx")]
+    #[test_case(404, "4xx")]
+    #[test_case(503, "5xx")]
+    #[test_case(999, "invalid")]
+    fn status_class_tests(status: u16, expected: &str) {
+        // Arrange
+
+        // Act
+        let actual = status_class(status);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("a+b%20c", 1, "a b c")]
+    #[test_case("%253Cscript%253E", 1, "%3Cscript%3E")]
+    #[test_case("%253Cscript%253E", 2, "<script>")]
+    #[test_case("plain", 2, "plain")]
+    fn percent_decode_tests(value: &str, passes: usize, expected: &str) {
+        // Arrange
+
+        // Act
+        let actual = percent_decode(value, passes);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("req", Some(LogParameter::Request))]
+    #[test_case("host", Some(LogParameter::Host))]
+    #[test_case("query_keys", Some(LogParameter::QueryKeys))]
+    #[test_case("query:utm_source", Some(LogParameter::Query("utm_source".to_owned())))]
+    #[test_case("query:", None)]
+    #[test_case("unknown", None)]
+    fn parameter_parse_tests(value: &str, expected: Option<LogParameter>) {
+        // Arrange
+        let cmd = clap::Command::new("test");
+
+        // Act
+        let actual = LogParameterParser
+            .parse_ref(&cmd, None, std::ffi::OsStr::new(value))
+            .ok();
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn query_parameter_displayed_with_name() {
+        // Arrange
+        let parameter = LogParameter::Query("token".to_owned());
+
+        // Act
+        let actual = parameter.to_string();
+
+        // Assert
+        assert_eq!(actual, "query:token");
+    }
 }
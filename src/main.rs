@@ -1,19 +1,46 @@
+use chrono::TimeDelta;
 use clap::{
-    Arg, ArgMatches, Command, arg, command, crate_authors, crate_description, crate_name,
-    crate_version, value_parser,
+    Arg, ArgAction, ArgMatches, Command, arg, command, crate_authors, crate_description,
+    crate_name, crate_version, value_parser,
 };
 use clap_complete::{Shell, generate};
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{Context, Result, bail};
 use indicatif::HumanBytes;
 use npma::{
-    GroupedParameter, LogEntry, LogParameter,
-    console::{self, print_grouped},
+    GroupedParameter, LogEntry, LogParameter, LogParameterParser,
+    anomaly::{AnomalyConfig, AnomalyDetector},
+    blocklist::{Blocklist, BlocklistFormat, parse_allowlist},
+    brokenlink::BrokenLinkDetector,
+    bruteforce::{BruteForceConfig, BruteForceDetector, DEFAULT_LOGIN_ENDPOINTS, LoginEndpoint},
+    calendar::{Calendar, CalendarMetric},
+    client::ClientTracker,
+    console::{
+        self, print_anomalies, print_attacks, print_broken_links, print_bruteforce, print_calendar,
+        print_client, print_grouped, print_heatmap, print_hotlinks, print_outages, print_pivot,
+        print_ratelimit, print_redirects, print_scanners, print_sessions, print_timeline,
+        print_visitors,
+    },
     convert,
+    endpoint::{Normalizer, parse_rewrite_rules},
     filter::Criteria,
+    heatmap::{Heatmap, HeatmapMetric, Zone},
+    hotlink::{DEFAULT_ASSET_EXTENSIONS, HotlinkDetector},
+    outage::{OutageConfig, OutageDetector},
+    parse_duration,
+    pivot::Crosstab,
+    ratelimit::{Rate, RateLimitConfig, RateLimitKey, RateLimitSimulator},
     read_strings_from_file, read_strings_from_stdin,
+    redirect::RedirectAnalyzer,
+    scanner::{BUNDLED_PROBES, ScannerConfig, ScannerDetector},
+    session::Sessions,
+    signature::{BUNDLED_SIGNATURES, Rule, SignatureScanner, parse_rules},
+    sketch::{HyperLogLog, SpaceSaving},
+    template::{DEFAULT_SIMILARITY, TemplateMiner},
+    timeline::Timeline,
+    visitors::{VisitorsCounter, VisitorsPeriod},
 };
 use std::io;
-use std::{collections::HashMap, pin::pin};
+use std::{cell::RefCell, collections::HashMap, pin::pin};
 use tokio_stream::{self, Stream, StreamExt};
 
 #[cfg(target_os = "linux")]
@@ -27,10 +54,30 @@ const PATH: &str = "PATH";
 const EXCLUDE_HELP: &str = "Exclude requests that match this pattern";
 const INCLUDE_HELP: &str = "Include only requests that match this pattern";
 const FILTER_PARAMETER_ARG: &str = "parameter";
+/// Number of distinct groups after which grouping switches to approximate mode
+const DEFAULT_MAX_GROUPS: usize = 1_000_000;
+/// Memory that exact grouping may spend on distinct values estimators before switching to approximate mode
+const DISTINCT_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+/// Minimal number of counters kept in approximate grouping mode
+const APPROXIMATE_CAPACITY: usize = 10_000;
+/// Default number of rows in reports top lists
+const DEFAULT_TOP: usize = 10;
+/// Minimal score of client to be reported as scanner by default
+const DEFAULT_MIN_SCORE: f64 = 10.0;
+/// Minimal number of attack signature matches of client to block it by default
+const DEFAULT_MIN_ATTACK_HITS: u64 = 3;
+/// Default ipset set name or fail2ban jail name
+const DEFAULT_BLOCKLIST_NAME: &str = "npma";
+/// Default number of internal referrers displayed for each broken link
+const DEFAULT_REFERRERS: usize = 5;
 
 struct ScanConfiguration {
     filter: Criteria,
     parameter: Option<LogParameter>,
+    /// Present only if endpoint parameter is used because normalization is relatively expensive
+    normalizer: Option<Normalizer>,
+    /// Present only if template parameter is used because mining is relatively expensive
+    miner: Option<RefCell<TemplateMiner>>,
 }
 
 #[tokio::main]
@@ -64,19 +111,46 @@ async fn scan_stdin(cmd: &ArgMatches) -> Result<()> {
 }
 
 async fn scan(entries: impl Stream<Item = String> + Unpin, cmd: &ArgMatches) -> Result<()> {
-    let config = configure_scan(cmd);
-    let stream = convert(entries, &config.filter, config.parameter);
+    let config = configure_scan(cmd).await?;
+    let stream = convert(
+        entries,
+        &config.filter,
+        config.parameter,
+        config.normalizer.as_ref(),
+        config.miner.as_ref(),
+    );
     let stream = pin!(stream);
-    print_converted(cmd, stream).await;
-    Ok(())
+    print_converted(cmd, stream, config.miner.as_ref()).await
 }
 
-async fn print_converted(cmd: &ArgMatches, entries: impl Stream<Item = LogEntry> + Unpin) {
+async fn print_converted(
+    cmd: &ArgMatches,
+    entries: impl Stream<Item = LogEntry> + Unpin,
+    miner: Option<&RefCell<TemplateMiner>>,
+) -> Result<()> {
     match cmd.subcommand() {
-        Some(("g", cmd)) => handle_group(cmd, entries).await,
+        Some(("g", cmd)) => handle_group(cmd, entries, miner).await,
         Some(("t", _)) => handle_traffic(entries).await,
+        Some(("hotlinks", cmd)) => handle_hotlinks(cmd, entries).await,
+        Some(("redirects", cmd)) => handle_redirects(cmd, entries).await,
+        Some(("pivot", cmd)) => handle_pivot(cmd, entries, miner).await,
+        Some(("timeline", cmd)) => handle_timeline(cmd, entries).await,
+        Some(("heatmap", cmd)) => handle_heatmap(cmd, entries).await,
+        Some(("calendar", cmd)) => handle_calendar(cmd, entries).await,
+        Some(("sessions", cmd)) => handle_sessions(cmd, entries).await,
+        Some(("client", cmd)) => handle_client(cmd, entries).await,
+        Some(("visitors", cmd)) => handle_visitors(cmd, entries).await,
+        Some(("anomalies", cmd)) => handle_anomalies(cmd, entries).await,
+        Some(("outages", cmd)) => handle_outages(cmd, entries).await,
+        Some(("scanners", cmd)) => handle_scanners(cmd, entries).await?,
+        Some(("attacks", cmd)) => handle_attacks(cmd, entries).await?,
+        Some(("broken-links", cmd)) => handle_broken_links(cmd, entries).await?,
+        Some(("bruteforce", cmd)) => handle_bruteforce(cmd, entries).await,
+        Some(("export-blocklist", cmd)) => handle_export_blocklist(cmd, entries).await?,
+        Some(("ratelimit-sim", cmd)) => handle_ratelimit(cmd, entries).await,
         _ => console::print(entries).await,
     }
+    Ok(())
 }
 
 async fn handle_traffic(mut entries: impl Stream<Item = LogEntry> + Unpin) {
@@ -87,34 +161,607 @@ async fn handle_traffic(mut entries: impl Stream<Item = LogEntry> + Unpin) {
     println!("Total traffic: {}", HumanBytes(total_bytes));
 }
 
-async fn handle_group(cmd: &ArgMatches, mut stream: impl Stream<Item = LogEntry> + Unpin) {
+async fn handle_hotlinks(cmd: &ArgMatches, mut entries: impl Stream<Item = LogEntry> + Unpin) {
+    let extensions: Vec<String> = match cmd.get_many::<String>("extensions") {
+        Some(extensions) => extensions.cloned().collect(),
+        None => DEFAULT_ASSET_EXTENSIONS.map(String::from).to_vec(),
+    };
+    let limit = *cmd.get_one::<usize>("top").unwrap_or(&DEFAULT_TOP);
+
+    let mut detector = HotlinkDetector::new(&extensions);
+    while let Some(e) = entries.next().await {
+        detector.add(&e);
+    }
+    print_hotlinks(&detector.finish(), limit);
+}
+
+async fn handle_redirects(cmd: &ArgMatches, mut entries: impl Stream<Item = LogEntry> + Unpin) {
+    let limit = *cmd.get_one::<usize>("top").unwrap_or(&DEFAULT_TOP);
+
+    let mut analyzer = RedirectAnalyzer::new();
+    while let Some(e) = entries.next().await {
+        analyzer.add(&e);
+    }
+    print_redirects(&analyzer.finish(), limit);
+}
+
+async fn handle_pivot(
+    cmd: &ArgMatches,
+    mut stream: impl Stream<Item = LogEntry> + Unpin,
+    miner: Option<&RefCell<TemplateMiner>>,
+) {
+    let limit = cmd.get_one::<usize>("top");
+    if let (Some(rows), Some(columns)) = (
+        cmd.get_one::<LogParameter>("rows"),
+        cmd.get_one::<LogParameter>("cols"),
+    ) {
+        let mut crosstab = Crosstab::new();
+        while let Some(entry) = stream.next().await {
+            crosstab.add(&rows.extract(&entry), &columns.extract(&entry));
+        }
+        if let Some(miner) = miner {
+            // templates assigned to entries may have become more general since
+            let miner = miner.borrow();
+            let resolve = |parameter: &LogParameter, key: &str| {
+                if *parameter == LogParameter::Template {
+                    miner.resolve(key)
+                } else {
+                    key.to_owned()
+                }
+            };
+            crosstab = crosstab.map_keys(|r| resolve(rows, r), |c| resolve(columns, c));
+        }
+        print_pivot(rows, columns, &crosstab, limit);
+    }
+}
+
+async fn handle_timeline(cmd: &ArgMatches, mut stream: impl Stream<Item = LogEntry> + Unpin) {
+    let bucket = cmd
+        .get_one::<TimeDelta>("bucket")
+        .copied()
+        .unwrap_or_else(|| TimeDelta::hours(1));
+    let mut timeline = Timeline::new(bucket);
+    while let Some(entry) = stream.next().await {
+        timeline.add(&entry);
+    }
+    print_timeline(&timeline, cmd.get_flag("split"));
+}
+
+async fn handle_heatmap(cmd: &ArgMatches, mut stream: impl Stream<Item = LogEntry> + Unpin) {
+    let metric = *cmd
+        .get_one::<HeatmapMetric>("metric")
+        .unwrap_or(&HeatmapMetric::Requests);
+    let zone = cmd.get_one::<Zone>("tz").copied();
+    let mut heatmap = Heatmap::new(metric, zone);
+    while let Some(entry) = stream.next().await {
+        heatmap.add(&entry);
+    }
+    print_heatmap(&heatmap);
+}
+
+async fn handle_calendar(cmd: &ArgMatches, mut stream: impl Stream<Item = LogEntry> + Unpin) {
+    let metric = *cmd
+        .get_one::<CalendarMetric>("metric")
+        .unwrap_or(&CalendarMetric::Requests);
+    let mut calendar = Calendar::new();
+    while let Some(entry) = stream.next().await {
+        calendar.add(&entry);
+    }
+    print_calendar(&calendar, metric);
+}
+
+async fn handle_sessions(cmd: &ArgMatches, mut stream: impl Stream<Item = LogEntry> + Unpin) {
+    let timeout = cmd
+        .get_one::<TimeDelta>("timeout")
+        .copied()
+        .unwrap_or_else(|| TimeDelta::minutes(30));
+    let limit = *cmd.get_one::<usize>("top").unwrap_or(&DEFAULT_TOP);
+    let mut sessions = Sessions::new(timeout);
+    while let Some(entry) = stream.next().await {
+        sessions.add(&entry);
+    }
+    print_sessions(&sessions.finish(), limit);
+}
+
+async fn handle_visitors(cmd: &ArgMatches, mut stream: impl Stream<Item = LogEntry> + Unpin) {
+    let period = *cmd
+        .get_one::<VisitorsPeriod>("period")
+        .unwrap_or(&VisitorsPeriod::Day);
+    let mut counter = VisitorsCounter::new(period, cmd.get_flag("exclude-bots"));
+    while let Some(entry) = stream.next().await {
+        counter.add(&entry);
+    }
+    print_visitors(&counter.finish());
+}
+
+async fn handle_client(cmd: &ArgMatches, mut stream: impl Stream<Item = LogEntry> + Unpin) {
+    let client = cmd.get_one::<String>("client").expect("client is required");
+    let gap = cmd
+        .get_one::<TimeDelta>("gap")
+        .copied()
+        .unwrap_or_else(|| TimeDelta::minutes(30));
+    let limit = *cmd.get_one::<usize>("top").unwrap_or(&DEFAULT_TOP);
+    let mut tracker = ClientTracker::new(client, gap);
+    while let Some(entry) = stream.next().await {
+        tracker.add(&entry);
+    }
+    print_client(&tracker.finish(), !cmd.get_flag("summary"), limit);
+}
+
+async fn handle_anomalies(cmd: &ArgMatches, mut stream: impl Stream<Item = LogEntry> + Unpin) {
+    let defaults = AnomalyConfig::default();
+    let config = AnomalyConfig {
+        bucket: cmd
+            .get_one::<TimeDelta>("bucket")
+            .copied()
+            .unwrap_or(defaults.bucket),
+        window: *cmd.get_one::<u32>("window").unwrap_or(&defaults.window),
+        threshold: *cmd
+            .get_one::<f64>("threshold")
+            .unwrap_or(&defaults.threshold),
+        min_requests: *cmd
+            .get_one::<u64>("min-requests")
+            .unwrap_or(&defaults.min_requests),
+    };
+    let limit = *cmd.get_one::<usize>("top").expect("top has default value");
+    let mut detector = AnomalyDetector::new(config, limit);
+    while let Some(entry) = stream.next().await {
+        detector.add(&entry);
+    }
+    print_anomalies(&detector.finish());
+}
+
+async fn handle_outages(cmd: &ArgMatches, mut stream: impl Stream<Item = LogEntry> + Unpin) {
+    let defaults = OutageConfig::default();
+    let config = OutageConfig {
+        bucket: cmd
+            .get_one::<TimeDelta>("bucket")
+            .copied()
+            .unwrap_or(defaults.bucket),
+        min_expected: *cmd
+            .get_one::<f64>("min-expected")
+            .unwrap_or(&defaults.min_expected),
+    };
+    let mut detector = OutageDetector::new(config);
+    while let Some(entry) = stream.next().await {
+        detector.add(&entry);
+    }
+    print_outages(&detector.finish());
+}
+
+async fn handle_scanners(
+    cmd: &ArgMatches,
+    mut stream: impl Stream<Item = LogEntry> + Unpin,
+) -> Result<()> {
+    let limit = *cmd.get_one::<usize>("top").unwrap_or(&DEFAULT_TOP);
+    let mut detector = ScannerDetector::new(scanner_config(cmd), &load_probes(cmd).await?)?;
+    while let Some(entry) = stream.next().await {
+        detector.add(&entry);
+    }
+    print_scanners(&detector.finish(min_score(cmd)), limit);
+    Ok(())
+}
+
+/// Bundled probe paths extended with paths from the file set by `--probes`
+async fn load_probes(cmd: &ArgMatches) -> Result<String> {
+    let mut probes = BUNDLED_PROBES.to_owned();
+    if let Some(path) = cmd.get_one::<String>("probes") {
+        let custom = tokio::fs::read_to_string(path)
+            .await
+            .wrap_err_with(|| format!("Probes file '{path}' cannot be read"))?;
+        probes.push('\n');
+        probes.push_str(&custom);
+    }
+    Ok(probes)
+}
+
+fn scanner_config(cmd: &ArgMatches) -> ScannerConfig {
+    let defaults = ScannerConfig::default();
+    ScannerConfig {
+        window: cmd
+            .get_one::<TimeDelta>("window")
+            .copied()
+            .unwrap_or(defaults.window),
+        ..defaults
+    }
+}
+
+fn min_score(cmd: &ArgMatches) -> f64 {
+    *cmd.get_one::<f64>("min-score")
+        .unwrap_or(&DEFAULT_MIN_SCORE)
+}
+
+async fn handle_attacks(
+    cmd: &ArgMatches,
+    mut stream: impl Stream<Item = LogEntry> + Unpin,
+) -> Result<()> {
+    let limit = *cmd.get_one::<usize>("top").unwrap_or(&DEFAULT_TOP);
+    let mut scanner = SignatureScanner::new(load_rules(cmd).await?, limit);
+    while let Some(entry) = stream.next().await {
+        scanner.add(&entry);
+    }
+    print_attacks(&scanner.finish());
+    Ok(())
+}
+
+/// Bundled attack signatures extended with rules from the file set by `--rules`
+async fn load_rules(cmd: &ArgMatches) -> Result<Vec<Rule>> {
+    let mut rules = parse_rules(BUNDLED_SIGNATURES)?;
+    if let Some(path) = cmd.get_one::<String>("rules") {
+        let custom = tokio::fs::read_to_string(path)
+            .await
+            .wrap_err_with(|| format!("Rules file '{path}' cannot be read"))?;
+        rules.extend(parse_rules(&custom).wrap_err_with(|| format!("Rules file '{path}'"))?);
+    }
+    Ok(rules)
+}
+
+async fn handle_broken_links(
+    cmd: &ArgMatches,
+    mut stream: impl Stream<Item = LogEntry> + Unpin,
+) -> Result<()> {
+    let probes = load_probes(cmd).await?;
+    let limit = *cmd.get_one::<usize>("top").unwrap_or(&DEFAULT_TOP);
+    let referrers = *cmd
+        .get_one::<usize>("referrers")
+        .unwrap_or(&DEFAULT_REFERRERS);
+
+    let mut detector = BrokenLinkDetector::new(&probes, min_score(cmd))?;
+    while let Some(entry) = stream.next().await {
+        detector.add(&entry);
+    }
+    let mut links = detector.finish();
+    if cmd.get_flag("internal") {
+        links.links.retain(|l| l.internal_hits > 0);
+    }
+    print_broken_links(&links, limit, referrers);
+    Ok(())
+}
+
+async fn handle_bruteforce(cmd: &ArgMatches, mut stream: impl Stream<Item = LogEntry> + Unpin) {
+    let limit = *cmd.get_one::<usize>("top").unwrap_or(&DEFAULT_TOP);
+    let mut detector = BruteForceDetector::new(bruteforce_config(cmd), login_endpoints(cmd));
+    while let Some(entry) = stream.next().await {
+        detector.add(&entry);
+    }
+    let (clients, windows) = detector.finish(limit);
+    print_bruteforce(&clients, &windows, limit);
+}
+
+/// Login endpoints set by `--endpoint` or common login paths
+fn login_endpoints(cmd: &ArgMatches) -> Vec<LoginEndpoint> {
+    match cmd.get_many::<LoginEndpoint>("endpoint") {
+        Some(endpoints) => endpoints.cloned().collect(),
+        None => DEFAULT_LOGIN_ENDPOINTS
+            .iter()
+            .filter_map(|e| e.parse().ok())
+            .collect(),
+    }
+}
+
+fn bruteforce_config(cmd: &ArgMatches) -> BruteForceConfig {
+    let defaults = BruteForceConfig::default();
+    BruteForceConfig {
+        window: cmd
+            .get_one::<TimeDelta>("window")
+            .copied()
+            .unwrap_or(defaults.window),
+        client_threshold: cmd
+            .get_one::<u64>("client-threshold")
+            .copied()
+            .unwrap_or(defaults.client_threshold),
+        window_threshold: cmd
+            .try_get_one::<u64>("window-threshold")
+            .ok()
+            .flatten()
+            .copied()
+            .unwrap_or(defaults.window_threshold),
+        min_clients: cmd
+            .try_get_one::<usize>("min-clients")
+            .ok()
+            .flatten()
+            .copied()
+            .unwrap_or(defaults.min_clients),
+    }
+}
+
+async fn handle_export_blocklist(
+    cmd: &ArgMatches,
+    mut stream: impl Stream<Item = LogEntry> + Unpin,
+) -> Result<()> {
+    let allowlist = match cmd.get_one::<String>("allowlist") {
+        Some(path) => {
+            let text = tokio::fs::read_to_string(path)
+                .await
+                .wrap_err_with(|| format!("Allowlist file '{path}' cannot be read"))?;
+            parse_allowlist(&text).wrap_err_with(|| format!("Allowlist file '{path}'"))?
+        }
+        None => vec![],
+    };
+    let mut blocklist = Blocklist::new(cmd.get_flag("aggregate"), allowlist);
+    match cmd.get_one::<String>("source").map(String::as_str) {
+        Some("scanners") => {
+            let mut detector = ScannerDetector::new(scanner_config(cmd), &load_probes(cmd).await?)?;
+            while let Some(entry) = stream.next().await {
+                detector.add(&entry);
+            }
+            for scanner in detector.finish(min_score(cmd)) {
+                blocklist.add(&scanner.client);
+            }
+        }
+        Some("attacks") => {
+            let min_hits = *cmd
+                .get_one::<u64>("min-hits")
+                .unwrap_or(&DEFAULT_MIN_ATTACK_HITS);
+            let mut scanner = SignatureScanner::new(load_rules(cmd).await?, 0);
+            while let Some(entry) = stream.next().await {
+                scanner.add(&entry);
+            }
+            let mut hits: HashMap<String, u64> = HashMap::new();
+            for (client, count) in scanner.finish().into_iter().flat_map(|m| m.clients) {
+                *hits.entry(client).or_default() += count;
+            }
+            for (client, _) in hits.iter().filter(|(_, h)| **h >= min_hits) {
+                blocklist.add(client);
+            }
+        }
+        Some("bruteforce") => {
+            let mut detector =
+                BruteForceDetector::new(bruteforce_config(cmd), login_endpoints(cmd));
+            while let Some(entry) = stream.next().await {
+                detector.add(&entry);
+            }
+            let (clients, _) = detector.finish(0);
+            for client in clients {
+                blocklist.add(&client.client);
+            }
+        }
+        _ => {
+            let min_requests = *cmd.get_one::<u64>("min-requests").unwrap_or(&1);
+            let mut requests: HashMap<String, u64> = HashMap::new();
+            while let Some(entry) = stream.next().await {
+                *requests.entry(entry.clientip).or_default() += 1;
+            }
+            for (client, _) in requests.iter().filter(|(_, r)| **r >= min_requests) {
+                blocklist.add(client);
+            }
+        }
+    }
+
+    let format = *cmd
+        .get_one::<BlocklistFormat>("format")
+        .unwrap_or(&BlocklistFormat::Plain);
+    let name = cmd
+        .get_one::<String>("name")
+        .map_or(DEFAULT_BLOCKLIST_NAME, String::as_str);
+    let content = blocklist.render(format, name);
+    if let Some(path) = cmd.get_one::<String>("output") {
+        tokio::fs::write(path, content)
+            .await
+            .wrap_err_with(|| format!("Block list file '{path}' cannot be written"))?;
+        println!("{} entries written to {path}", blocklist.len());
+    } else {
+        print!("{content}");
+    }
+    Ok(())
+}
+
+async fn handle_ratelimit(cmd: &ArgMatches, mut stream: impl Stream<Item = LogEntry> + Unpin) {
+    let config = RateLimitConfig {
+        rate: *cmd.get_one::<Rate>("rate").expect("rate is required"),
+        burst: *cmd.get_one::<u64>("burst").unwrap_or(&0),
+        key: *cmd
+            .get_one::<RateLimitKey>("key")
+            .unwrap_or(&RateLimitKey::Client),
+    };
+    let limit = *cmd.get_one::<usize>("top").unwrap_or(&DEFAULT_TOP);
+
+    let mut simulator = RateLimitSimulator::new(config);
+    while let Some(entry) = stream.next().await {
+        simulator.add(&entry);
+    }
+    print_ratelimit(&simulator.finish(), limit);
+}
+
+enum Groups {
+    Exact(HashMap<String, (u64, HyperLogLog)>),
+    Approximate(SpaceSaving<HyperLogLog>),
+}
+
+async fn handle_group(
+    cmd: &ArgMatches,
+    mut stream: impl Stream<Item = LogEntry> + Unpin,
+    miner: Option<&RefCell<TemplateMiner>>,
+) {
     let limit = cmd.get_one::<usize>("top");
+    let distinct = cmd.get_one::<LogParameter>("distinct");
+    let chart = cmd.get_flag("chart");
+    // each group keeps its own estimator so the number of groups is limited by memory budget
+    let default_max_groups = if distinct.is_some() {
+        DISTINCT_MEMORY_BUDGET / HyperLogLog::MAX_BYTES
+    } else {
+        DEFAULT_MAX_GROUPS
+    };
+    let max_groups = *cmd
+        .get_one::<usize>("max-groups")
+        .unwrap_or(&default_max_groups);
+    let capacity = limit.map_or(APPROXIMATE_CAPACITY, |l| {
+        l.saturating_mul(10).max(APPROXIMATE_CAPACITY)
+    });
     if let Some(param) = cmd.get_one::<LogParameter>(FILTER_PARAMETER_ARG) {
-        let mut counts: HashMap<String, u64> = HashMap::new();
+        let mut groups = if cmd.get_flag("approx") {
+            Groups::Approximate(SpaceSaving::new(capacity))
+        } else {
+            Groups::Exact(HashMap::new())
+        };
+        let mut total = 0u64;
         while let Some(entry) = stream.next().await {
-            let key = param.extract(&entry).into_owned();
-            counts.entry(key).and_modify(|c| *c += 1).or_insert(1);
+            total += 1;
+            let key = param.extract(&entry);
+            let sketch = match &mut groups {
+                Groups::Exact(counts) => {
+                    let (count, sketch) = counts.entry(key.into_owned()).or_default();
+                    *count += 1;
+                    sketch
+                }
+                Groups::Approximate(summary) => &mut summary.insert(&key).payload,
+            };
+            if let Some(distinct) = distinct {
+                sketch.insert(distinct.extract(&entry).as_ref());
+            }
+            // switch to bounded memory mode when there are too many distinct keys
+            if let Groups::Exact(counts) = &mut groups
+                && counts.len() > max_groups
+            {
+                let counts = std::mem::take(counts)
+                    .into_iter()
+                    .map(|(key, (count, sketch))| (key, count, sketch));
+                groups = Groups::Approximate(SpaceSaving::from_exact(capacity, counts));
+            }
         }
 
-        let grouped = counts
+        let (mut grouped, approximated_total): (Vec<Group>, _) = match groups {
+            Groups::Exact(counts) => (
+                counts
+                    .into_iter()
+                    .map(|(key, (count, sketch))| (key, count, None, sketch))
+                    .collect(),
+                None,
+            ),
+            Groups::Approximate(summary) => (
+                summary
+                    .into_counters()
+                    .into_iter()
+                    .map(|c| (c.key, c.count, Some(c.error), c.payload))
+                    .collect(),
+                Some(total),
+            ),
+        };
+        if *param == LogParameter::Template
+            && let Some(miner) = miner
+        {
+            grouped = resolve_templates(grouped, &miner.borrow());
+        }
+        let grouped = grouped
             .into_iter()
-            .map(|(parameter, count)| GroupedParameter { parameter, count });
+            .map(|(parameter, count, error, sketch)| GroupedParameter {
+                parameter,
+                count,
+                distinct: distinct.map(|_| sketch.estimate()),
+                error,
+            });
+        print_grouped(param, distinct, grouped, limit, approximated_total, chart);
+    }
+}
 
-        print_grouped(*param, grouped, limit);
+/// Group key, count, count error and distinct values sketch
+type Group = (String, u64, Option<u64>, HyperLogLog);
+
+/// Merges groups of templates that became more general after they were assigned to entries
+fn resolve_templates(groups: Vec<Group>, miner: &TemplateMiner) -> Vec<Group> {
+    let mut resolved: HashMap<String, (u64, Option<u64>, HyperLogLog)> = HashMap::new();
+    for (key, count, error, sketch) in groups {
+        let (total, total_error, merged) = resolved
+            .entry(miner.resolve(&key))
+            .or_insert_with(|| (0, error.map(|_| 0), HyperLogLog::new()));
+        *total += count;
+        if let (Some(total_error), Some(error)) = (total_error.as_mut(), error) {
+            *total_error += error;
+        }
+        merged.merge(&sketch);
     }
+    resolved
+        .into_iter()
+        .map(|(key, (count, error, sketch))| (key, count, error, sketch))
+        .collect()
+}
+
+/// Whether parameter is used by filter or subcommand
+fn uses_parameter(cmd: &ArgMatches, parameter: LogParameter) -> bool {
+    let used = |cmd: &ArgMatches, id: &str| {
+        cmd.try_get_one::<LogParameter>(id)
+            .ok()
+            .flatten()
+            .is_some_and(|p| *p == parameter)
+    };
+    used(cmd, FILTER_PARAMETER_ARG)
+        || cmd.subcommand().is_some_and(|(_, cmd)| {
+            [FILTER_PARAMETER_ARG, "distinct", "rows", "cols"]
+                .iter()
+                .any(|id| used(cmd, id))
+        })
 }
 
 /// Creates application configuration from parsed command line
-fn configure_scan(cmd: &ArgMatches) -> ScanConfiguration {
+async fn configure_scan(cmd: &ArgMatches) -> Result<ScanConfiguration> {
+    // templates are resolved into final ones when groups or pivot table are output,
+    // filter and distinct values estimators use values at once
+    let is_template = |cmd: &ArgMatches, id: &str| {
+        cmd.try_get_one::<LogParameter>(id)
+            .ok()
+            .flatten()
+            .is_some_and(|p| *p == LogParameter::Template)
+    };
+    if is_template(cmd, FILTER_PARAMETER_ARG)
+        || cmd
+            .subcommand()
+            .is_some_and(|(_, cmd)| is_template(cmd, "distinct"))
+    {
+        bail!("template parameter can be used only for grouping and pivot table rows or columns");
+    }
+    if let Some(("export-blocklist", export)) = cmd.subcommand()
+        && export
+            .get_one::<String>("source")
+            .is_none_or(|s| s == "clients")
+        && !cmd.contains_id("include")
+        && !cmd.contains_id("exclude")
+        && !export.contains_id("min-requests")
+    {
+        bail!(
+            "clients source requires filter (-i or -e) or --min-requests otherwise every client of the log is blocked"
+        );
+    }
+    if (cmd.contains_id("endpoint-rules") || cmd.get_flag("strip-query"))
+        && !uses_parameter(cmd, LogParameter::Endpoint)
+    {
+        bail!("--endpoint-rules and --strip-query can be used only with endpoint parameter");
+    }
     let include_pattern = cmd.get_one::<String>("include");
     let exclude_pattern = cmd.get_one::<String>("exclude");
-    let parameter = cmd.get_one::<LogParameter>(FILTER_PARAMETER_ARG).copied();
+    let parameter = cmd.get_one::<LogParameter>(FILTER_PARAMETER_ARG).cloned();
 
     let filter = Criteria::new(
         include_pattern.map(String::as_str),
         exclude_pattern.map(String::as_str),
     );
-    ScanConfiguration { filter, parameter }
+    let normalizer = if uses_parameter(cmd, LogParameter::Endpoint) {
+        let rules = match cmd.get_one::<String>("endpoint-rules") {
+            Some(path) => {
+                let text = tokio::fs::read_to_string(path)
+                    .await
+                    .wrap_err_with(|| format!("Endpoint rules file '{path}' cannot be read"))?;
+                parse_rewrite_rules(&text)
+                    .wrap_err_with(|| format!("Endpoint rules file '{path}'"))?
+            }
+            None => vec![],
+        };
+        Some(Normalizer::new(rules, cmd.get_flag("strip-query")))
+    } else {
+        None
+    };
+    let miner = uses_parameter(cmd, LogParameter::Template).then(|| {
+        let similarity = cmd
+            .get_one::<f64>("template-similarity")
+            .copied()
+            .unwrap_or(DEFAULT_SIMILARITY);
+        RefCell::new(TemplateMiner::new(similarity))
+    });
+    Ok(ScanConfiguration {
+        filter,
+        parameter,
+        normalizer,
+        miner,
+    })
 }
 
 fn build_cli() -> Command {
@@ -149,8 +796,28 @@ fn file_cmd() -> Command {
         .arg(exclude_arg())
         .arg(include_arg())
         .arg(parameter_arg())
+        .arg(endpoint_rules_arg())
+        .arg(strip_query_arg())
+        .arg(template_similarity_arg())
         .subcommand(grouping_cmd())
         .subcommand(traffic_cmd())
+        .subcommand(hotlinks_cmd())
+        .subcommand(redirects_cmd())
+        .subcommand(pivot_cmd())
+        .subcommand(timeline_cmd())
+        .subcommand(heatmap_cmd())
+        .subcommand(calendar_cmd())
+        .subcommand(sessions_cmd())
+        .subcommand(client_cmd())
+        .subcommand(visitors_cmd())
+        .subcommand(anomalies_cmd())
+        .subcommand(outages_cmd())
+        .subcommand(scanners_cmd())
+        .subcommand(attacks_cmd())
+        .subcommand(broken_links_cmd())
+        .subcommand(bruteforce_cmd())
+        .subcommand(export_blocklist_cmd())
+        .subcommand(ratelimit_cmd())
 }
 
 fn stdin_cmd() -> Command {
@@ -160,8 +827,28 @@ fn stdin_cmd() -> Command {
         .arg(exclude_arg())
         .arg(include_arg())
         .arg(parameter_arg())
+        .arg(endpoint_rules_arg())
+        .arg(strip_query_arg())
+        .arg(template_similarity_arg())
         .subcommand(grouping_cmd())
         .subcommand(traffic_cmd())
+        .subcommand(hotlinks_cmd())
+        .subcommand(redirects_cmd())
+        .subcommand(pivot_cmd())
+        .subcommand(timeline_cmd())
+        .subcommand(heatmap_cmd())
+        .subcommand(calendar_cmd())
+        .subcommand(sessions_cmd())
+        .subcommand(client_cmd())
+        .subcommand(visitors_cmd())
+        .subcommand(anomalies_cmd())
+        .subcommand(outages_cmd())
+        .subcommand(scanners_cmd())
+        .subcommand(attacks_cmd())
+        .subcommand(broken_links_cmd())
+        .subcommand(bruteforce_cmd())
+        .subcommand(export_blocklist_cmd())
+        .subcommand(ratelimit_cmd())
 }
 
 fn exclude_arg() -> Arg {
@@ -180,10 +867,29 @@ fn include_arg() -> Arg {
 
 fn parameter_arg() -> Arg {
     arg!(-p --parameter <PARAMETER>)
-        .value_parser(value_parser!(LogParameter))
+        .value_parser(LogParameterParser)
         .help("Filter parameter")
 }
 
+fn endpoint_rules_arg() -> Arg {
+    arg!(--"endpoint-rules" <FILE>)
+        .required(false)
+        .help("File with rules that rewrite request path into endpoint. Each line is regex and replacement separated by whitespace")
+}
+
+fn strip_query_arg() -> Arg {
+    arg!(--"strip-query")
+        .required(false)
+        .help("Remove query string from endpoint")
+}
+
+fn template_similarity_arg() -> Arg {
+    arg!(--"template-similarity" <NUMBER>)
+        .required(false)
+        .value_parser(value_parser!(f64))
+        .help("Minimal share of equal path segments to join path into existing URL template (0.5 by default)")
+}
+
 fn completion_cmd() -> Command {
     Command::new("completion")
         .about("Generate the autocompletion script for the specified shell")
@@ -205,9 +911,33 @@ fn grouping_cmd() -> Command {
                 .value_parser(value_parser!(usize))
                 .help("Output only specified number of grouped items"),
         )
+        .arg(
+            arg!(-d --distinct <PARAMETER>)
+                .required(false)
+                .value_parser(LogParameterParser)
+                .help("Estimate the number of unique values of this parameter within each group"),
+        )
+        .arg(
+            arg!(-a --approx)
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Count only most frequent groups using bounded memory. Counts become approximate"),
+        )
+        .arg(
+            arg!(--"max-groups" <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("Switch to approximate mode when the number of groups exceeds this value (1000000 by default, 65536 with --distinct)"),
+        )
+        .arg(
+            arg!(-c --chart)
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Draw horizontal bar chart next to each group"),
+        )
         .arg(
             arg!([parameter])
-                .value_parser(value_parser!(LogParameter))
+                .value_parser(LogParameterParser)
                 .required(true)
                 .index(1),
         )
@@ -218,3 +948,466 @@ fn traffic_cmd() -> Command {
         .aliases(["traffic"])
         .about("Sums all log entries length to calculate all data size passed through proxy")
 }
+
+fn hotlinks_cmd() -> Command {
+    Command::new("hotlinks")
+        .aliases(["hl"])
+        .about("Sums traffic of static assets (images, video, archives) requested with external referrers and ranks referring domains by bytes served")
+        .arg(
+            arg!(-x --extensions <EXTENSIONS>)
+                .required(false)
+                .value_delimiter(',')
+                .help("Comma separated extensions of static assets instead of default images, video and archives"),
+        )
+        .arg(
+            arg!(-t --top <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("Output only specified number of referring domains (10 by default)"),
+        )
+}
+
+fn redirects_cmd() -> Command {
+    Command::new("redirects")
+        .aliases(["rd"])
+        .about("Counts 301, 302, 307 and 308 responses by host and path along with the share of plain http requests answered with redirects")
+        .arg(
+            arg!(-t --top <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("Output only specified number of paths with the most redirects (10 by default)"),
+        )
+}
+
+fn pivot_cmd() -> Command {
+    Command::new("pivot")
+        .aliases(["crosstab"])
+        .about("Counts log entries by two parameters and displays them as a matrix with row and column totals")
+        .arg(
+            arg!(-r --rows <PARAMETER>)
+                .required(true)
+                .value_parser(LogParameterParser)
+                .help("Parameter which values are used as rows"),
+        )
+        .arg(
+            arg!(-c --cols <PARAMETER>)
+                .required(true)
+                .value_parser(LogParameterParser)
+                .help("Parameter which values are used as columns"),
+        )
+        .arg(
+            arg!(-t --top <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("Output only specified number of rows with the biggest totals"),
+        )
+}
+
+fn timeline_cmd() -> Command {
+    Command::new("timeline")
+        .aliases(["tl"])
+        .about("Draws the number of requests per time bucket as a chart")
+        .arg(
+            arg!(-b --bucket <DURATION>)
+                .required(false)
+                .value_parser(parse_duration)
+                .help("Bucket size like 30s, 15m, 1h, 1d or 1w (1h by default)"),
+        )
+        .arg(
+            Arg::new("split")
+                .short('s')
+                .long("split")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Split requests by status class"),
+        )
+}
+
+fn heatmap_cmd() -> Command {
+    Command::new("heatmap")
+        .aliases(["hm"])
+        .about("Draws day of week by hour of day grid shaded by the number of requests, traffic or errors")
+        .arg(
+            arg!(-m --metric <METRIC>)
+                .required(false)
+                .value_parser(value_parser!(HeatmapMetric))
+                .help("Value summed in grid cells (requests by default)"),
+        )
+        .arg(
+            arg!(--tz <ZONE>)
+                .required(false)
+                .value_parser(str::parse::<Zone>)
+                .help("Convert timestamps into time zone like Europe/Berlin or UTC offset like +03:00 instead of log time zone"),
+        )
+}
+
+fn calendar_cmd() -> Command {
+    Command::new("calendar")
+        .aliases(["cal"])
+        .about("Draws weeks by weekdays calendar of daily requests or error rate to overview long periods of logs")
+        .arg(
+            arg!(-m --metric <METRIC>)
+                .required(false)
+                .value_parser(value_parser!(CalendarMetric))
+                .help("Value that days are shaded by (requests by default)"),
+        )
+}
+
+fn sessions_cmd() -> Command {
+    Command::new("sessions")
+        .aliases(["s"])
+        .about("Reconstructs visitor sessions (client IP and user agent) and displays sessions statistics")
+        .arg(
+            arg!(--timeout <DURATION>)
+                .required(false)
+                .value_parser(parse_duration)
+                .help("Inactivity time after which session ends (30m by default)"),
+        )
+        .arg(
+            arg!(-t --top <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("Output only specified number of entry and exit paths (10 by default)"),
+        )
+}
+
+fn visitors_cmd() -> Command {
+    Command::new("visitors")
+        .aliases(["vi"])
+        .about("Counts unique visitors (client IP and user agent) per day or week split into new and returning ones")
+        .arg(
+            arg!(--period <PERIOD>)
+                .required(false)
+                .value_parser(value_parser!(VisitorsPeriod))
+                .help("Period visitors are counted within (day by default)"),
+        )
+        .arg(
+            arg!(-b --"exclude-bots")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Skip requests of crawlers, HTTP libraries and other non-browser user agents"),
+        )
+}
+
+fn client_cmd() -> Command {
+    Command::new("client")
+        .aliases(["cl"])
+        .about("Displays everything client did in chronological order with pauses marked along with hosts, statuses and user agents summaries")
+        .arg(
+            arg!(-g --gap <DURATION>)
+                .required(false)
+                .value_parser(parse_duration)
+                .help("Minimal pause between requests to mark in timeline (30m by default)"),
+        )
+        .arg(
+            arg!(-s --summary)
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Output only summaries without requests"),
+        )
+        .arg(
+            arg!(-t --top <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("Output only specified number of hosts, statuses and user agents (10 by default)"),
+        )
+        .arg(
+            arg!(<client>)
+                .required(true)
+                .help("Client IP address"),
+        )
+}
+
+fn anomalies_cmd() -> Command {
+    Command::new("anomalies")
+        .aliases(["an"])
+        .about("Finds time buckets which requests count, error rate or traffic deviate from the baseline for all requests, each host and each client. Log entries are expected to be in chronological order")
+        .arg(
+            arg!(-b --bucket <DURATION>)
+                .required(false)
+                .value_parser(parse_duration)
+                .help("Bucket size like 5m, 1h or 1d (1h by default)"),
+        )
+        .arg(
+            arg!(-w --window <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(u32).range(1..))
+                .help("The number of buckets used to calculate baseline (24 by default)"),
+        )
+        .arg(
+            arg!(--threshold <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(f64))
+                .help("Deviation from baseline in standard deviations to report bucket (4 by default)"),
+        )
+        .arg(
+            arg!(--"min-requests" <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(u64))
+                .help("Minimal number of requests in bucket to report client spike or error rate growth (100 by default)"),
+        )
+        .arg(
+            arg!(-t --top <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .default_value("3")
+                .help("The number of top contributing clients and paths to display"),
+        )
+}
+
+fn outages_cmd() -> Command {
+    Command::new("outages")
+        .aliases(["ou"])
+        .about("Finds intervals without log entries or with 5xx responses only for all requests and each host. Intervals are reported when normal traffic of their hours of day makes absence of requests unlikely")
+        .arg(
+            arg!(-b --bucket <DURATION>)
+                .required(false)
+                .value_parser(parse_duration)
+                .help("Bucket size like 1m, 5m or 1h (5m by default)"),
+        )
+        .arg(
+            arg!(--"min-expected" <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(f64))
+                .help("Minimal number of requests expected within interval by normal traffic to report it (10 by default)"),
+        )
+}
+
+fn scanners_cmd() -> Command {
+    Command::new("scanners")
+        .aliases(["sc"])
+        .about("Scores clients by the likelihood that they're vulnerability scanners using known probe paths, not found responses ratio and bursts of distinct paths")
+        .arg(
+            arg!(--probes <FILE>)
+                .required(false)
+                .help("File with additional probe paths one per line. Paths are matched case-insensitively against path segments, ^ anchors path to the beginning of the request path"),
+        )
+        .arg(
+            arg!(-w --window <DURATION>)
+                .required(false)
+                .value_parser(parse_duration)
+                .help("Window to count distinct paths requested by client (1m by default)"),
+        )
+        .arg(
+            arg!(--"min-score" <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(f64))
+                .help("Minimal score of client to be reported (10 by default)"),
+        )
+        .arg(
+            arg!(-t --top <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("Output only specified number of clients with the biggest scores (10 by default)"),
+        )
+}
+
+fn broken_links_cmd() -> Command {
+    Command::new("broken-links")
+        .aliases(["bk"])
+        .about("Lists paths answered with 404 or 410 along with internal pages that link to them. Known probe paths and clients detected as scanners are excluded")
+        .arg(
+            arg!(--probes <FILE>)
+                .required(false)
+                .help("File with additional probe paths one per line. Paths are matched case-insensitively against path segments, ^ anchors path to the beginning of the request path"),
+        )
+        .arg(
+            arg!(--"min-score" <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(f64))
+                .help("Minimal scanner score of client to exclude its requests (10 by default)"),
+        )
+        .arg(
+            arg!(--internal)
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .help("Output only paths linked from own pages"),
+        )
+        .arg(
+            arg!(-r --referrers <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("The number of internal referrers displayed for each path (5 by default)"),
+        )
+        .arg(
+            arg!(-t --top <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("Output only specified number of paths with the most internal hits (10 by default)"),
+        )
+}
+
+fn attacks_cmd() -> Command {
+    Command::new("attacks")
+        .aliases(["at"])
+        .about("Finds SQL injection, XSS, path traversal, command injection and other attack signatures in percent-decoded request, user agent and referrer")
+        .arg(
+            arg!(--rules <FILE>)
+                .required(false)
+                .help("File with additional rules. See README for the rules format"),
+        )
+        .arg(
+            arg!(-t --top <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("The number of sample matches displayed for each rule (10 by default)"),
+        )
+}
+
+fn bruteforce_cmd() -> Command {
+    Command::new("bruteforce")
+        .aliases(["bf"])
+        .about("Detects single client brute force and distributed credential stuffing by failed (401, 403, 429) requests to login endpoints")
+        .arg(
+            arg!(--endpoint <ENDPOINT>)
+                .required(false)
+                .action(ArgAction::Append)
+                .value_parser(value_parser!(LoginEndpoint))
+                .help("Login endpoint like 'POST /api/login' or '/login'. Path ending with * matches as prefix. Can be specified several times. Common login paths used by default"),
+        )
+        .arg(
+            arg!(-w --window <DURATION>)
+                .required(false)
+                .value_parser(parse_duration)
+                .help("Window to count failed attempts (5m by default)"),
+        )
+        .arg(
+            arg!(--"client-threshold" <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(u64))
+                .help("Failed attempts of single client within window to report it (10 by default)"),
+        )
+        .arg(
+            arg!(--"window-threshold" <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(u64))
+                .help("Failed attempts to endpoint within window to report window (50 by default)"),
+        )
+        .arg(
+            arg!(--"min-clients" <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("Minimal number of distinct failing clients to report window (5 by default)"),
+        )
+        .arg(
+            arg!(-t --top <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("Output only specified number of clients and windows (10 by default)"),
+        )
+}
+
+fn export_blocklist_cmd() -> Command {
+    Command::new("export-blocklist")
+        .aliases(["bl"])
+        .about("Writes client addresses as nginx deny directives, ipset or fail2ban commands or plain list")
+        .arg(
+            arg!(-f --format <FORMAT>)
+                .required(false)
+                .value_parser(value_parser!(BlocklistFormat))
+                .help("Block list format (plain by default)"),
+        )
+        .arg(
+            arg!(-s --source <SOURCE>)
+                .required(false)
+                .value_parser(["clients", "scanners", "attacks", "bruteforce"])
+                .help("Clients to block: clients of filtered entries or clients detected by scanners, attacks or bruteforce reports (clients by default)"),
+        )
+        .arg(
+            arg!(--"min-requests" <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(u64).range(1..))
+                .help("clients source: minimal number of filtered requests of client to block it. Required unless entries are filtered"),
+        )
+        .arg(
+            arg!(--probes <FILE>)
+                .required(false)
+                .help("scanners source: file with additional probe paths one per line"),
+        )
+        .arg(
+            arg!(--"min-score" <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(f64))
+                .help("scanners source: minimal score of client to block it (10 by default)"),
+        )
+        .arg(
+            arg!(--rules <FILE>)
+                .required(false)
+                .help("attacks source: file with additional rules"),
+        )
+        .arg(
+            arg!(--"min-hits" <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(u64).range(1..))
+                .help("attacks source: minimal number of signature matches of client to block it (3 by default)"),
+        )
+        .arg(
+            arg!(--endpoint <ENDPOINT>)
+                .required(false)
+                .action(ArgAction::Append)
+                .value_parser(value_parser!(LoginEndpoint))
+                .help("bruteforce source: login endpoint like 'POST /api/login' or '/login'. Can be specified several times"),
+        )
+        .arg(
+            arg!(--"client-threshold" <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(u64))
+                .help("bruteforce source: failed attempts of single client within window to block it (10 by default). Clients of distributed attacks aren't exported"),
+        )
+        .arg(
+            arg!(-w --window <DURATION>)
+                .required(false)
+                .value_parser(parse_duration)
+                .help("Window of scanners source to count distinct paths (1m by default) or bruteforce source to count failed attempts (5m by default)"),
+        )
+        .arg(
+            arg!(-a --aggregate)
+                .required(false)
+                .help("Aggregate addresses to /24 (IPv4) and /64 (IPv6) networks unless network contains allowed address"),
+        )
+        .arg(
+            arg!(--allowlist <FILE>)
+                .required(false)
+                .help("File with addresses and networks one per line that are never emitted"),
+        )
+        .arg(
+            arg!(-n --name <NAME>)
+                .required(false)
+                .help("ipset set name or fail2ban jail name (npma by default)"),
+        )
+        .arg(
+            arg!(-o --output <FILE>)
+                .required(false)
+                .help("Write block list into file instead of standard output"),
+        )
+}
+
+fn ratelimit_cmd() -> Command {
+    Command::new("ratelimit-sim")
+        .aliases(["rl"])
+        .about("Replays requests in timestamp order through the same leaky bucket as nginx limit_req and shows who would be rejected and when")
+        .arg(
+            arg!(-r --rate <RATE>)
+                .required(true)
+                .value_parser(value_parser!(Rate))
+                .help("Requests rate like 10r/s or 30r/m"),
+        )
+        .arg(
+            arg!(-b --burst <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(u64))
+                .help("Maximum burst size (0 by default)"),
+        )
+        .arg(
+            arg!(-k --key <KEY>)
+                .required(false)
+                .value_parser(value_parser!(RateLimitKey))
+                .help("Requests limited together (client by default)"),
+        )
+        .arg(
+            arg!(-t --top <NUMBER>)
+                .required(false)
+                .value_parser(value_parser!(usize))
+                .help("Output only specified number of keys with the most rejected requests (10 by default)"),
+        )
+}
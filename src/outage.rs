@@ -0,0 +1,483 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+
+use chrono::{DateTime, FixedOffset, Offset, TimeDelta, Utc};
+
+use crate::LogEntry;
+
+const HOURS_IN_DAY: usize = 24;
+const SECONDS_IN_DAY: i64 = 86_400;
+const SECONDS_IN_HOUR: i64 = 3_600;
+
+/// Outages detection settings
+#[derive(Debug, Clone, Copy)]
+pub struct OutageConfig {
+    /// Time bucket size. Outages shorter than bucket may be missed
+    pub bucket: TimeDelta,
+    /// Minimal number of requests expected within interval by normal traffic of its hours of day
+    /// to report it. Quiet intervals are expected to be without requests sometimes
+    pub min_expected: f64,
+}
+
+impl Default for OutageConfig {
+    fn default() -> Self {
+        Self {
+            bucket: TimeDelta::minutes(5),
+            min_expected: 10.0,
+        }
+    }
+}
+
+/// Traffic the outage was found in
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum OutageScope {
+    All,
+    Host(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutageKind {
+    /// No log entries at all
+    NoEntries,
+    /// Every response was 5xx
+    ServerErrors,
+}
+
+/// Interval without successful responses though normal traffic was expected
+#[derive(Debug)]
+pub struct Outage {
+    pub scope: OutageScope,
+    pub kind: OutageKind,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    /// 5xx responses within interval
+    pub errors: u64,
+    /// Requests expected within interval by average traffic of the same hours of day
+    pub expected: f64,
+}
+
+impl Outage {
+    #[must_use]
+    pub fn duration(&self) -> TimeDelta {
+        self.end - self.start
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Bucket {
+    requests: u64,
+    server_errors: u64,
+    /// UTC offset of bucket entries in seconds
+    offset: i32,
+}
+
+impl Bucket {
+    fn is_down(self) -> bool {
+        self.requests == self.server_errors
+    }
+}
+
+/// Consecutive buckets of the same hour of day. Buckets without entries are merged into runs so
+/// that gaps in log take time proportional to their hours rather than to the number of buckets
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    start: i64,
+    /// The number of buckets. Only runs without entries have more than one
+    count: i64,
+    hour: usize,
+    bucket: Bucket,
+}
+
+/// Finds intervals without log entries or with 5xx responses only for all traffic and each host.
+/// Entries may come in any order so several log files can be analyzed together. Buckets are aligned
+/// to UTC while hours of day are taken in the offset of bucket entries so that daylight saving time
+/// changes don't shift normal traffic baselines
+#[derive(Debug)]
+pub struct OutageDetector {
+    config: OutageConfig,
+    buckets: HashMap<OutageScope, BTreeMap<i64, Bucket>>,
+}
+
+/// Consecutive buckets without successful responses
+#[derive(Debug, Default)]
+struct Interval {
+    /// The first bucket where requests were expected and its UTC offset
+    start: Option<(i64, i32)>,
+    /// End of the last bucket where requests were expected
+    end: i64,
+    requests: u64,
+    errors: u64,
+    expected: f64,
+}
+
+impl OutageDetector {
+    #[must_use]
+    pub fn new(config: OutageConfig) -> Self {
+        Self {
+            config,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Adds entry into its bucket. Entries without timestamp are ignored
+    pub fn add(&mut self, entry: &LogEntry) {
+        if !entry.has_timestamp() {
+            return;
+        }
+        let bucket = self.bucket_seconds();
+        let start = entry.timestamp.timestamp().div_euclid(bucket) * bucket;
+        for scope in [OutageScope::All, OutageScope::Host(entry.host.clone())] {
+            let bucket = self
+                .buckets
+                .entry(scope)
+                .or_default()
+                .entry(start)
+                .or_default();
+            bucket.requests += 1;
+            bucket.server_errors += u64::from(entry.status >= 500);
+            bucket.offset = entry.timestamp.offset().local_minus_utc();
+        }
+    }
+
+    /// Returns outages in chronological order. Host intervals without entries are not reported
+    /// when there are no entries of all hosts either because that means log is missing
+    #[must_use]
+    pub fn finish(self) -> Vec<Outage> {
+        let mut outages = vec![];
+        for (scope, buckets) in &self.buckets {
+            self.find_outages(scope, buckets, &mut outages);
+        }
+        let missing: Vec<_> = outages
+            .iter()
+            .filter(|o| o.scope == OutageScope::All && o.kind == OutageKind::NoEntries)
+            .map(|o| (o.start, o.end))
+            .collect();
+        outages.retain(|o| {
+            o.scope == OutageScope::All
+                || o.kind != OutageKind::NoEntries
+                || !missing
+                    .iter()
+                    .any(|(start, end)| *start <= o.start && o.end <= *end)
+        });
+        outages.sort_unstable_by(|a, b| a.start.cmp(&b.start).then_with(|| a.scope.cmp(&b.scope)));
+        outages
+    }
+
+    /// Finds intervals of buckets without successful responses from the first to the last bucket
+    /// of the scope comparing them with average requests per bucket of the same hours of day
+    #[allow(clippy::cast_precision_loss)]
+    fn find_outages(
+        &self,
+        scope: &OutageScope,
+        buckets: &BTreeMap<i64, Bucket>,
+        outages: &mut Vec<Outage>,
+    ) {
+        let step = self.bucket_seconds();
+        let mut hours = [(0u64, 0u64); HOURS_IN_DAY];
+        runs(buckets, step, |run| {
+            let (count, requests) = &mut hours[run.hour];
+            *count += run.count.unsigned_abs();
+            *requests += run.bucket.requests;
+        });
+        let expected = |hour: usize| {
+            let (count, requests) = hours[hour];
+            requests as f64 / count.max(1) as f64
+        };
+        let time = |start: i64, offset: i32| {
+            let offset = FixedOffset::east_opt(offset).unwrap_or(Utc.fix());
+            DateTime::from_timestamp(start, 0)
+                .unwrap_or_default()
+                .with_timezone(&offset)
+        };
+        let mut report = |interval: Interval| {
+            if let Some((begin, offset)) = interval.start
+                && interval.expected >= self.config.min_expected
+            {
+                outages.push(Outage {
+                    scope: scope.clone(),
+                    kind: if interval.requests == 0 {
+                        OutageKind::NoEntries
+                    } else {
+                        OutageKind::ServerErrors
+                    },
+                    start: time(begin, offset),
+                    end: time(interval.end, offset),
+                    errors: interval.errors,
+                    expected: interval.expected,
+                });
+            }
+        };
+
+        let mut current: Option<Interval> = None;
+        runs(buckets, step, |run| {
+            if !run.bucket.is_down() {
+                if let Some(interval) = current.take() {
+                    report(interval);
+                }
+                return;
+            }
+            let expected = expected(run.hour);
+            let interval = current.get_or_insert_default();
+            interval.requests += run.bucket.requests;
+            interval.errors += run.bucket.server_errors;
+            // quiet buckets around outage are not part of it
+            if run.bucket.requests > 0 || expected >= 1.0 {
+                interval.start.get_or_insert((run.start, run.bucket.offset));
+                interval.end = run.start + run.count * step;
+                interval.expected += expected * run.count as f64;
+            }
+        });
+        if let Some(interval) = current {
+            report(interval);
+        }
+    }
+
+    fn bucket_seconds(&self) -> i64 {
+        self.config.bucket.num_seconds().max(1)
+    }
+}
+
+/// Calls `f` for runs of buckets from the first to the last one in chronological order.
+/// Buckets without entries take the offset of the previous bucket
+fn runs(buckets: &BTreeMap<i64, Bucket>, step: i64, mut f: impl FnMut(Run)) {
+    let mut next: Option<(i64, i32)> = None;
+    for (start, bucket) in buckets {
+        if let Some((mut empty, offset)) = next {
+            let offset = i64::from(offset);
+            while empty < *start {
+                let hour_end =
+                    ((empty + offset).div_euclid(SECONDS_IN_HOUR) + 1) * SECONDS_IN_HOUR - offset;
+                let count = (hour_end.min(*start) - empty + step - 1) / step;
+                f(Run {
+                    start: empty,
+                    count,
+                    hour: hour_of_day(empty, offset),
+                    bucket: Bucket {
+                        offset: i32::try_from(offset).unwrap_or_default(),
+                        ..Default::default()
+                    },
+                });
+                empty += count * step;
+            }
+        }
+        f(Run {
+            start: *start,
+            count: 1,
+            hour: hour_of_day(*start, i64::from(bucket.offset)),
+            bucket: *bucket,
+        });
+        next = Some((start + step, bucket.offset));
+    }
+}
+
+fn hour_of_day(start: i64, offset: i64) -> usize {
+    let local = (start + offset).rem_euclid(SECONDS_IN_DAY);
+    usize::try_from(local / SECONDS_IN_HOUR).unwrap_or_default()
+}
+
+impl Display for OutageScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutageScope::All => write!(f, "all"),
+            OutageScope::Host(host) => write!(f, "host {host}"),
+        }
+    }
+}
+
+impl Display for OutageKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutageKind::NoEntries => write!(f, "No entries"),
+            OutageKind::ServerErrors => write!(f, "5xx only"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone};
+
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(host: &str, minutes: i64, status: u16) -> LogEntry {
+        LogEntryBuilder::new()
+            .host(host)
+            .status(status)
+            .after(TimeDelta::minutes(minutes))
+            .build()
+    }
+
+    /// Three days of traffic: 20 requests per 5 minutes during day hours and quiet nights
+    fn traffic(detector: &mut OutageDetector, host: &str, skip: impl Fn(i64) -> Option<u16>) {
+        for day in 0..3 {
+            for hour in 8..20 {
+                for minute in (0..60).step_by(5) {
+                    let minutes = day * 1440 + hour * 60 + minute;
+                    let status = skip(minutes).unwrap_or(200);
+                    if status == 0 {
+                        continue;
+                    }
+                    for _ in 0..20 {
+                        detector.add(&entry(host, minutes, status));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn quiet_nights_not_reported() {
+        // Arrange
+        let mut detector = OutageDetector::new(OutageConfig::default());
+
+        // Act
+        traffic(&mut detector, "example.com", |_| None);
+        let actual = detector.finish();
+
+        // Assert
+        assert!(actual.is_empty(), "{actual:?}");
+    }
+
+    #[test]
+    fn daylight_saving_time_change_not_reported() {
+        // Arrange
+        let mut detector = OutageDetector::new(OutageConfig::default());
+        let start = NaiveDate::from_ymd_opt(2024, 3, 28)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+
+        // Act
+        // the same local hours of traffic before and after switching from +01:00 to +02:00
+        for day in 0..6 {
+            let offset = FixedOffset::east_opt(if day < 3 { 3600 } else { 7200 }).unwrap();
+            for minutes in (8 * 60..20 * 60).step_by(5) {
+                let local = start + TimeDelta::days(day) + TimeDelta::minutes(minutes);
+                let mut e = entry("example.com", 0, 200);
+                e.timestamp = offset.from_local_datetime(&local).unwrap();
+                for _ in 0..20 {
+                    detector.add(&e);
+                }
+            }
+        }
+        let actual = detector.finish();
+
+        // Assert
+        assert!(actual.is_empty(), "{actual:?}");
+    }
+
+    #[test]
+    fn host_down_and_server_errors_found() {
+        // Arrange
+        let mut detector = OutageDetector::new(OutageConfig::default());
+
+        // Act
+        traffic(&mut detector, "example.com", |m| {
+            (1440 + 600..1440 + 630).contains(&m).then_some(0)
+        });
+        traffic(&mut detector, "api.example.com", |m| {
+            (2880 + 720..2880 + 735).contains(&m).then_some(502)
+        });
+        let actual = detector.finish();
+
+        // Assert
+        let actual: Vec<_> = actual
+            .iter()
+            .map(|o| {
+                (
+                    o.scope.to_string(),
+                    o.kind,
+                    o.start.to_rfc3339(),
+                    o.duration().num_minutes(),
+                    o.errors,
+                )
+            })
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                (
+                    "host example.com".to_owned(),
+                    OutageKind::NoEntries,
+                    "2024-05-02T10:00:00+00:00".to_owned(),
+                    30,
+                    0
+                ),
+                (
+                    "host api.example.com".to_owned(),
+                    OutageKind::ServerErrors,
+                    "2024-05-03T12:00:00+00:00".to_owned(),
+                    15,
+                    60
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn long_gap_merged_into_hourly_runs() {
+        // Arrange
+        let year = 365 * SECONDS_IN_DAY;
+        let offset = 3600;
+        let buckets = BTreeMap::from([
+            (
+                0,
+                Bucket {
+                    requests: 1,
+                    server_errors: 0,
+                    offset,
+                },
+            ),
+            (
+                year,
+                Bucket {
+                    requests: 1,
+                    server_errors: 0,
+                    offset,
+                },
+            ),
+        ]);
+
+        // Act
+        let mut actual = vec![];
+        runs(&buckets, 1, |run| actual.push(run));
+
+        // Assert
+        assert_eq!(actual.len(), 365 * 24 + 2);
+        assert_eq!(actual.iter().map(|r| r.count).sum::<i64>(), year + 1);
+        assert_eq!(
+            (actual[1].start, actual[1].count, actual[1].hour),
+            (1, 3599, 1)
+        );
+        assert_eq!(
+            (actual[2].start, actual[2].count, actual[2].hour),
+            (3600, 3600, 2)
+        );
+        assert!(
+            actual
+                .windows(2)
+                .all(|w| w[0].start + w[0].count == w[1].start)
+        );
+    }
+
+    #[test]
+    fn missing_log_reported_once() {
+        // Arrange
+        let mut detector = OutageDetector::new(OutageConfig::default());
+
+        // Act
+        for host in ["example.com", "api.example.com"] {
+            traffic(&mut detector, host, |m| {
+                (1440 + 480..1440 + 1200).contains(&m).then_some(0)
+            });
+        }
+        let actual = detector.finish();
+
+        // Assert
+        assert_eq!(actual.len(), 1, "{actual:?}");
+        assert_eq!(actual[0].scope, OutageScope::All);
+        assert_eq!(actual[0].kind, OutageKind::NoEntries);
+        assert_eq!(actual[0].duration(), TimeDelta::hours(12));
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// Cross tabulation of entries counts by two parameters
+#[derive(Default, Debug)]
+pub struct Crosstab {
+    cells: HashMap<String, BTreeMap<String, u64>>,
+    column_totals: BTreeMap<String, u64>,
+    total: u64,
+}
+
+/// Crosstab row with its cells in the order of `Crosstab::columns`
+#[derive(Debug, PartialEq, Eq)]
+pub struct CrosstabRow<'a> {
+    pub key: &'a str,
+    pub cells: Vec<u64>,
+    pub total: u64,
+}
+
+impl Crosstab {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, row: &str, column: &str) {
+        let cells = if let Some(cells) = self.cells.get_mut(row) {
+            cells
+        } else {
+            self.cells.entry(row.to_owned()).or_default()
+        };
+        if let Some(c) = cells.get_mut(column) {
+            *c += 1;
+        } else {
+            cells.insert(column.to_owned(), 1);
+        }
+        if let Some(t) = self.column_totals.get_mut(column) {
+            *t += 1;
+        } else {
+            self.column_totals.insert(column.to_owned(), 1);
+        }
+        self.total += 1;
+    }
+
+    /// Replaces row and column keys with keys returned by `rows` and `columns` merging counts
+    /// of the keys that became equal
+    #[must_use]
+    pub fn map_keys(self, rows: impl Fn(&str) -> String, columns: impl Fn(&str) -> String) -> Self {
+        let mut result = Self {
+            total: self.total,
+            ..Self::default()
+        };
+        for (row, cells) in self.cells {
+            let merged = result.cells.entry(rows(&row)).or_default();
+            for (column, count) in cells {
+                *merged.entry(columns(&column)).or_default() += count;
+            }
+        }
+        for (column, count) in self.column_totals {
+            *result.column_totals.entry(columns(&column)).or_default() += count;
+        }
+        result
+    }
+
+    /// Columns sorted by their keys
+    pub fn columns(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.column_totals.iter().map(|(k, v)| (k.as_str(), *v))
+    }
+
+    /// Rows sorted by their totals descending
+    #[must_use]
+    pub fn rows(&self) -> Vec<CrosstabRow<'_>> {
+        let mut rows: Vec<_> = self
+            .cells
+            .iter()
+            .map(|(key, cells)| CrosstabRow {
+                key,
+                cells: self
+                    .column_totals
+                    .keys()
+                    .map(|c| cells.get(c).copied().unwrap_or_default())
+                    .collect(),
+                total: cells.values().sum(),
+            })
+            .collect();
+        rows.sort_unstable_by(|a, b| b.total.cmp(&a.total).then_with(|| a.key.cmp(b.key)));
+        rows
+    }
+
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crosstab_rows_and_columns() {
+        // Arrange
+        let mut crosstab = Crosstab::new();
+
+        // Act
+        crosstab.add("b.com", "404");
+        crosstab.add("a.com", "200");
+        crosstab.add("a.com", "200");
+        crosstab.add("a.com", "404");
+
+        // Assert
+        let columns: Vec<_> = crosstab.columns().collect();
+        assert_eq!(columns, vec![("200", 2), ("404", 2)]);
+        assert_eq!(
+            crosstab.rows(),
+            vec![
+                CrosstabRow {
+                    key: "a.com",
+                    cells: vec![2, 1],
+                    total: 3
+                },
+                CrosstabRow {
+                    key: "b.com",
+                    cells: vec![0, 1],
+                    total: 1
+                },
+            ]
+        );
+        assert_eq!(crosstab.total(), 4);
+    }
+
+    #[test]
+    fn crosstab_empty() {
+        // Arrange
+        let crosstab = Crosstab::new();
+
+        // Act
+        let rows = crosstab.rows();
+
+        // Assert
+        assert!(rows.is_empty());
+        assert_eq!(crosstab.columns().count(), 0);
+        assert_eq!(crosstab.total(), 0);
+    }
+
+    #[test]
+    fn crosstab_keys_mapped() {
+        // Arrange
+        let mut crosstab = Crosstab::new();
+        crosstab.add("/api/orders/1", "2xx");
+        crosstab.add("/api/orders/<*>", "2xx");
+        crosstab.add("/api/orders/<*>", "5xx");
+
+        // Act
+        let actual = crosstab.map_keys(|r| r.replace('1', "<*>"), str::to_owned);
+
+        // Assert
+        assert_eq!(
+            actual.rows(),
+            vec![CrosstabRow {
+                key: "/api/orders/<*>",
+                cells: vec![2, 1],
+                total: 3
+            }]
+        );
+        assert_eq!(actual.total(), 3);
+    }
+}
@@ -0,0 +1,102 @@
+use std::borrow::Cow;
+
+use percent_encoding::percent_decode_str;
+
+use crate::{percent_decode, request_path};
+
+/// Percent-decoded request path without query string
+#[must_use]
+pub fn path(request: &str) -> Cow<'_, str> {
+    percent_decode_str(request_path(request)).decode_utf8_lossy()
+}
+
+/// Percent-decoded query parameters names and values in the order of appearance
+pub fn parameters(request: &str) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, str>)> {
+    let query = request
+        .split_once('?')
+        .map_or("", |(_, query)| query)
+        .split('#')
+        .next()
+        .unwrap_or_default();
+    query.split('&').filter(|p| !p.is_empty()).map(|p| {
+        let (name, value) = p.split_once('=').unwrap_or((p, ""));
+        (percent_decode(name, 1), percent_decode(value, 1))
+    })
+}
+
+/// Sorted distinct query parameters names separated by comma
+#[must_use]
+pub fn keys(request: &str) -> String {
+    let mut keys: Vec<_> = parameters(request).map(|(name, _)| name).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    keys.join(",")
+}
+
+/// Values of query parameter separated by comma or empty string if there is no such parameter
+#[must_use]
+pub fn value<'a>(request: &'a str, name: &str) -> Cow<'a, str> {
+    let mut values = parameters(request)
+        .filter(|(n, _)| n == name)
+        .map(|(_, value)| value);
+    let Some(first) = values.next() else {
+        return Cow::Borrowed("");
+    };
+    values.fold(first, |mut result, value| {
+        let result_mut = result.to_mut();
+        result_mut.push(',');
+        result_mut.push_str(&value);
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("/api/users?id=1", "/api/users")]
+    #[test_case("/files/my%20file.txt", "/files/my file.txt")]
+    #[test_case("/", "/")]
+    fn path_tests(request: &str, expected: &str) {
+        // Arrange
+
+        // Act
+        let actual = path(request);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("/search?q=a&page=2&q=b", "page,q")]
+    #[test_case("/search?utm%5Fsource=x&", "utm_source")]
+    #[test_case("/search?flag", "flag")]
+    #[test_case("/search", "")]
+    fn keys_tests(request: &str, expected: &str) {
+        // Arrange
+
+        // Act
+        let actual = keys(request);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("/?utm_source=google&x=1", "utm_source", "google")]
+    #[test_case("/?q=hello+world%21", "q", "hello world!")]
+    #[test_case("/?tag=a&tag=b", "tag", "a,b")]
+    #[test_case("/?token=abc#top", "token", "abc")]
+    #[test_case("/?flag", "flag", "")]
+    #[test_case("/?x=1", "token", "")]
+    #[test_case("/", "token", "")]
+    fn value_tests(request: &str, name: &str, expected: &str) {
+        // Arrange
+
+        // Act
+        let actual = value(request, name);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+}
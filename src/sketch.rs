@@ -0,0 +1,363 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// Number of bits used to select a register. 2^12 registers give about 1.6% standard error
+const PRECISION: u32 = 12;
+const REGISTERS: usize = 1 << PRECISION;
+/// Until this number of distinct hashes is reached they're kept as is so small sets are counted exactly
+const SPARSE_LIMIT: usize = REGISTERS / 8;
+
+/// `HyperLogLog` cardinality estimator with bounded memory usage
+#[derive(Debug, Clone)]
+pub struct HyperLogLog {
+    repr: Representation,
+}
+
+#[derive(Debug, Clone)]
+enum Representation {
+    Sparse(Vec<u64>),
+    Dense(Vec<u8>),
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HyperLogLog {
+    /// Maximum memory used by estimator in bytes. Both sparse and dense representations fit into it
+    pub const MAX_BYTES: usize = REGISTERS;
+
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            repr: Representation::Sparse(Vec::new()),
+        }
+    }
+
+    pub fn insert<T: Hash + ?Sized>(&mut self, value: &T) {
+        self.insert_hash(hash_of(value));
+    }
+
+    /// Adds all values of another estimator so the result estimates the union of both sets
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        match &other.repr {
+            Representation::Sparse(hashes) => {
+                for hash in hashes {
+                    self.insert_hash(*hash);
+                }
+            }
+            Representation::Dense(other_registers) => {
+                if let Representation::Sparse(hashes) = &self.repr {
+                    let mut registers = vec![0u8; REGISTERS];
+                    for h in hashes {
+                        update_registers(&mut registers, *h);
+                    }
+                    self.repr = Representation::Dense(registers);
+                }
+                if let Representation::Dense(registers) = &mut self.repr {
+                    for (r, o) in registers.iter_mut().zip(other_registers) {
+                        *r = (*r).max(*o);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Adds value hashed by [`hash_of`] already
+    pub(crate) fn insert_hash(&mut self, hash: u64) {
+        match &mut self.repr {
+            Representation::Sparse(hashes) => {
+                if let Err(ix) = hashes.binary_search(&hash) {
+                    hashes.insert(ix, hash);
+                }
+                if hashes.len() > SPARSE_LIMIT {
+                    let mut registers = vec![0u8; REGISTERS];
+                    for h in hashes.iter() {
+                        update_registers(&mut registers, *h);
+                    }
+                    self.repr = Representation::Dense(registers);
+                }
+            }
+            Representation::Dense(registers) => update_registers(registers, hash),
+        }
+    }
+
+    /// Estimated number of distinct values inserted
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn estimate(&self) -> u64 {
+        match &self.repr {
+            Representation::Sparse(hashes) => hashes.len() as u64,
+            Representation::Dense(registers) => {
+                let m = REGISTERS as f64;
+                let alpha = 0.7213 / (1.0 + 1.079 / m);
+                let sum: f64 = registers.iter().map(|r| 2f64.powi(-i32::from(*r))).sum();
+                let raw = alpha * m * m / sum;
+                let zeros: usize = registers.iter().map(|r| usize::from(*r == 0)).sum();
+                let estimate = if raw <= 2.5 * m && zeros > 0 {
+                    // linear counting is more accurate for small cardinalities
+                    m * (m / zeros as f64).ln()
+                } else {
+                    raw
+                };
+                estimate.round() as u64
+            }
+        }
+    }
+}
+
+pub(crate) fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn update_registers(registers: &mut [u8], hash: u64) {
+    let ix = (hash >> (64 - PRECISION)) as usize;
+    let rest = hash << PRECISION;
+    #[allow(clippy::cast_possible_truncation)]
+    let rank = (rest.leading_zeros().min(64 - PRECISION) + 1) as u8;
+    if registers[ix] < rank {
+        registers[ix] = rank;
+    }
+}
+
+/// Counter of the `SpaceSaving` summary
+#[derive(Debug)]
+pub struct Counter<P> {
+    pub key: String,
+    /// Estimated count. It is never less than real one
+    pub count: u64,
+    /// Maximum overestimation of the count
+    pub error: u64,
+    pub payload: P,
+}
+
+/// Space-Saving heavy hitters summary. Keeps at most `capacity` counters so memory
+/// stays bounded whatever the number of distinct keys is. Any key which real count is
+/// greater than `total / capacity` is guaranteed to be in the summary.
+#[derive(Debug)]
+pub struct SpaceSaving<P> {
+    capacity: usize,
+    counters: Vec<Counter<P>>,
+    index: HashMap<String, usize>,
+    /// Counters ordered by count to find minimal one quickly
+    order: BTreeSet<(u64, usize)>,
+}
+
+impl<P: Default> SpaceSaving<P> {
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            counters: Vec::new(),
+            index: HashMap::new(),
+            order: BTreeSet::new(),
+        }
+    }
+
+    /// Creates summary from exact counts. Only `capacity` biggest counts are kept
+    #[must_use]
+    pub fn from_exact(capacity: usize, counts: impl IntoIterator<Item = (String, u64, P)>) -> Self {
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_unstable_by(|a, b| Ord::cmp(&b.1, &a.1));
+        let mut summary = Self::new(capacity);
+        for (key, count, payload) in counts.into_iter().take(summary.capacity) {
+            summary.push(Counter {
+                key,
+                count,
+                error: 0,
+                payload,
+            });
+        }
+        summary
+    }
+
+    /// Counts key occurrence and returns its counter
+    ///
+    /// # Panics
+    ///
+    /// Doesn't panic because capacity is never zero so full summary always has minimal counter
+    pub fn insert(&mut self, key: &str) -> &mut Counter<P> {
+        let ix = if let Some(ix) = self.index.get(key).copied() {
+            let counter = &mut self.counters[ix];
+            self.order.remove(&(counter.count, ix));
+            counter.count += 1;
+            self.order.insert((counter.count, ix));
+            ix
+        } else if self.counters.len() < self.capacity {
+            self.push(Counter {
+                key: key.to_owned(),
+                count: 1,
+                error: 0,
+                payload: P::default(),
+            })
+        } else {
+            // replace minimal counter with the new key
+            let (min, ix) = self.order.pop_first().expect("summary is not empty");
+            let counter = &mut self.counters[ix];
+            self.index.remove(&counter.key);
+            key.clone_into(&mut counter.key);
+            counter.count = min + 1;
+            counter.error = min;
+            counter.payload = P::default();
+            self.index.insert(counter.key.clone(), ix);
+            self.order.insert((counter.count, ix));
+            ix
+        };
+        &mut self.counters[ix]
+    }
+
+    fn push(&mut self, counter: Counter<P>) -> usize {
+        let ix = self.counters.len();
+        self.index.insert(counter.key.clone(), ix);
+        self.order.insert((counter.count, ix));
+        self.counters.push(counter);
+        ix
+    }
+
+    #[must_use]
+    pub fn into_counters(self) -> Vec<Counter<P>> {
+        self.counters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test]
+    fn hyperloglog_empty() {
+        // Arrange
+        let hll = HyperLogLog::new();
+
+        // Act
+        let actual = hll.estimate();
+
+        // Assert
+        assert_eq!(actual, 0);
+    }
+
+    #[test]
+    fn hyperloglog_duplicates_counted_once() {
+        // Arrange
+        let mut hll = HyperLogLog::new();
+
+        // Act
+        for _ in 0..10 {
+            hll.insert("a");
+            hll.insert("b");
+        }
+
+        // Assert
+        assert_eq!(hll.estimate(), 2);
+    }
+
+    #[test_case(100)]
+    #[test_case(1_000)]
+    #[test_case(10_000)]
+    #[test_case(100_000)]
+    fn hyperloglog_estimate_within_error(cardinality: u64) {
+        // Arrange
+        let mut hll = HyperLogLog::new();
+
+        // Act
+        for i in 0..cardinality {
+            hll.insert(&format!("10.0.{i}"));
+        }
+
+        // Assert
+        let actual = hll.estimate().abs_diff(cardinality);
+        assert!(actual <= cardinality / 20, "error {actual} too big");
+    }
+
+    #[test_case(10, 20)]
+    #[test_case(10, 10_000)]
+    #[test_case(10_000, 10)]
+    #[test_case(10_000, 20_000)]
+    fn hyperloglog_merge_estimates_union(first: u64, second: u64) {
+        // Arrange
+        let mut a = HyperLogLog::new();
+        let mut b = HyperLogLog::new();
+        for i in 0..first {
+            a.insert(&i);
+        }
+        for i in first / 2..first / 2 + second {
+            b.insert(&i);
+        }
+        let expected = first.max(first / 2 + second);
+
+        // Act
+        a.merge(&b);
+
+        // Assert
+        let actual = a.estimate().abs_diff(expected);
+        assert!(actual <= expected / 20, "error {actual} too big");
+    }
+
+    #[test]
+    fn space_saving_exact_when_capacity_enough() {
+        // Arrange
+        let mut summary: SpaceSaving<()> = SpaceSaving::new(10);
+
+        // Act
+        for key in ["a", "b", "a", "c", "a", "b"] {
+            summary.insert(key);
+        }
+
+        // Assert
+        let mut counters = summary.into_counters();
+        counters.sort_unstable_by(|a, b| Ord::cmp(&b.count, &a.count));
+        let actual: Vec<_> = counters
+            .iter()
+            .map(|c| (c.key.as_str(), c.count, c.error))
+            .collect();
+        assert_eq!(actual, vec![("a", 3, 0), ("b", 2, 0), ("c", 1, 0)]);
+    }
+
+    #[test]
+    fn space_saving_keeps_heavy_hitters() {
+        // Arrange
+        let mut summary: SpaceSaving<()> = SpaceSaving::new(10);
+
+        // Act
+        for i in 0..10_000 {
+            summary.insert(&format!("unique{i}"));
+            if i % 2 == 0 {
+                summary.insert("heavy");
+            }
+        }
+
+        // Assert
+        let counters = summary.into_counters();
+        assert_eq!(counters.len(), 10);
+        let heavy = counters.iter().find(|c| c.key == "heavy").unwrap();
+        assert!(heavy.count >= 5_000);
+        assert!(heavy.count - heavy.error <= 5_000);
+    }
+
+    #[test]
+    fn space_saving_from_exact_keeps_biggest() {
+        // Arrange
+        let counts = vec![
+            ("a".to_owned(), 1, ()),
+            ("b".to_owned(), 5, ()),
+            ("c".to_owned(), 3, ()),
+        ];
+
+        // Act
+        let mut summary = SpaceSaving::from_exact(2, counts);
+        let counter = summary.insert("a");
+
+        // Assert
+        assert_eq!(counter.count, 4);
+        assert_eq!(counter.error, 3);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use crate::request_path;
+
+/// Placeholder of variable path segment
+pub const WILDCARD: &str = "<*>";
+/// Default minimal share of equal segments to add path into existing template
+pub const DEFAULT_SIMILARITY: f64 = 0.5;
+/// The number of leading path segments used to route path to template candidates
+const ROUTING_DEPTH: usize = 2;
+/// Maximum number of children of a routing node. Other segments are routed to wildcard node
+const MAX_CHILDREN: usize = 100;
+
+/// Mines URL templates like `/api/orders/<*>/items` from request paths using Drain algorithm.
+/// Paths are routed by the number of segments and leading segments into groups of templates.
+/// Path joins the most similar template of the group if the share of equal segments isn't less
+/// than similarity threshold and different segments of the template become wildcards.
+/// Otherwise new template is created
+#[derive(Debug)]
+pub struct TemplateMiner {
+    similarity: f64,
+    templates: Vec<Vec<String>>,
+    tree: HashMap<usize, Node>,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: HashMap<String, Node>,
+    templates: Vec<usize>,
+}
+
+impl Default for TemplateMiner {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIMILARITY)
+    }
+}
+
+impl TemplateMiner {
+    #[must_use]
+    pub fn new(similarity: f64) -> Self {
+        Self {
+            similarity,
+            templates: vec![],
+            tree: HashMap::new(),
+        }
+    }
+
+    /// Adds request path and returns its template. Template may become more general
+    /// when more paths are added, use `resolve` to get the final one
+    pub fn add(&mut self, request: &str) -> String {
+        let segments = split(request);
+        let node = route(&mut self.tree, &segments);
+        let best = node
+            .templates
+            .iter()
+            .map(|ix| (*ix, similarity(&self.templates[*ix], &segments)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, s)| *s >= self.similarity);
+        let ix = if let Some((ix, _)) = best {
+            for (template, segment) in self.templates[ix].iter_mut().zip(&segments) {
+                if template != segment {
+                    WILDCARD.clone_into(template);
+                }
+            }
+            ix
+        } else {
+            self.templates
+                .push(segments.iter().map(|s| (*s).to_owned()).collect());
+            node.templates.push(self.templates.len() - 1);
+            self.templates.len() - 1
+        };
+        join(&self.templates[ix])
+    }
+
+    /// Returns the current template that covers template or path returned by `add` earlier
+    #[must_use]
+    pub fn resolve(&self, template: &str) -> String {
+        let segments = split(template);
+        let mut node = self.tree.get(&segments.len());
+        for segment in segments.iter().take(ROUTING_DEPTH.min(segments.len() - 1)) {
+            node = node.and_then(|n| {
+                n.children
+                    .get(routing_key(segment))
+                    .or_else(|| n.children.get(WILDCARD))
+            });
+        }
+        node.and_then(|n| {
+            n.templates.iter().map(|ix| &self.templates[*ix]).find(|t| {
+                t.iter()
+                    .zip(&segments)
+                    .all(|(t, s)| t == WILDCARD || t == s)
+            })
+        })
+        .map_or_else(|| template.to_owned(), |t| join(t))
+    }
+}
+
+fn split(request: &str) -> Vec<&str> {
+    request_path(request)
+        .strip_prefix('/')
+        .unwrap_or(request_path(request))
+        .split('/')
+        .collect()
+}
+
+fn join(segments: &[String]) -> String {
+    format!("/{}", segments.join("/"))
+}
+
+/// Segments with digits are likely variables so they're routed together
+fn routing_key(segment: &str) -> &str {
+    if segment.contains(|c: char| c.is_ascii_digit()) {
+        WILDCARD
+    } else {
+        segment
+    }
+}
+
+/// Finds leaf node by the number of segments and leading segments except the last one
+fn route<'a>(tree: &'a mut HashMap<usize, Node>, segments: &[&str]) -> &'a mut Node {
+    let mut node = tree.entry(segments.len()).or_default();
+    for segment in segments.iter().take(ROUTING_DEPTH.min(segments.len() - 1)) {
+        let mut key = routing_key(segment);
+        if !node.children.contains_key(key) && node.children.len() >= MAX_CHILDREN {
+            key = WILDCARD;
+        }
+        node = node.children.entry(key.to_owned()).or_default();
+    }
+    node
+}
+
+/// Share of equal segments. Wildcards aren't counted as equal
+#[allow(clippy::cast_precision_loss)]
+fn similarity(template: &[String], segments: &[&str]) -> f64 {
+    let equal = template
+        .iter()
+        .zip(segments)
+        .filter(|(t, s)| t == *s)
+        .count();
+    equal as f64 / segments.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test]
+    fn similar_paths_clustered() {
+        // Arrange
+        let mut miner = TemplateMiner::default();
+
+        // Act
+        let first = miner.add("/api/orders/17/items");
+        let second = miner.add("/api/orders/42/items?page=2");
+        let third = miner.add("/api/orders/a1b2/items");
+
+        // Assert
+        assert_eq!(first, "/api/orders/17/items");
+        assert_eq!(second, "/api/orders/<*>/items");
+        assert_eq!(third, "/api/orders/<*>/items");
+        assert_eq!(miner.resolve(&first), "/api/orders/<*>/items");
+    }
+
+    #[test_case("/about", "/contact")]
+    #[test_case("/api/orders/1/items", "/api/orders/1/items/2")]
+    #[test_case("/api/orders/1/items", "/api/users/1/roles")]
+    fn different_paths_not_clustered(first: &str, second: &str) {
+        // Arrange
+        let mut miner = TemplateMiner::default();
+
+        // Act
+        miner.add(first);
+        let actual = miner.add(second);
+
+        // Assert
+        assert_eq!(actual, request_path(second));
+        assert_eq!(miner.resolve(first), first);
+    }
+
+    #[test]
+    fn similarity_threshold_used() {
+        // Arrange
+        let mut miner = TemplateMiner::new(0.9);
+
+        // Act
+        miner.add("/blog/first-post");
+        let actual = miner.add("/blog/second-post");
+
+        // Assert
+        assert_eq!(actual, "/blog/second-post");
+    }
+
+    #[test]
+    fn unknown_template_resolved_as_is() {
+        // Arrange
+        let miner = TemplateMiner::default();
+
+        // Act
+        let actual = miner.resolve("/x/y");
+
+        // Assert
+        assert_eq!(actual, "/x/y");
+    }
+
+    #[test_case("", "/")]
+    #[test_case("/", "/")]
+    #[test_case("/a/", "/a/")]
+    fn edge_paths_tests(request: &str, expected: &str) {
+        // Arrange
+        let mut miner = TemplateMiner::default();
+
+        // Act
+        let actual = miner.add(request);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, FixedOffset, TimeDelta};
+
+use crate::{LogEntry, STATUS_CLASSES, status_class_index};
+
+/// Maximum number of timeline buckets. Displaying more of them makes no sense
+pub const MAX_BUCKETS: i64 = 1000;
+
+/// Requests counts split by fixed size time buckets
+#[derive(Debug)]
+pub struct Timeline {
+    bucket: i64,
+    offset: Option<FixedOffset>,
+    buckets: BTreeMap<i64, [u64; STATUS_CLASSES.len()]>,
+}
+
+/// Timeline bucket with requests counts per status class in `STATUS_CLASSES` order
+#[derive(Debug, PartialEq, Eq)]
+pub struct Bucket {
+    pub start: DateTime<FixedOffset>,
+    pub classes: [u64; STATUS_CLASSES.len()],
+}
+
+impl Bucket {
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.classes.iter().sum()
+    }
+}
+
+impl Timeline {
+    #[must_use]
+    pub fn new(bucket: TimeDelta) -> Self {
+        Self {
+            bucket: bucket.num_seconds().max(1),
+            offset: None,
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    /// Adds entry into its bucket. Entries without timestamp are ignored
+    pub fn add(&mut self, entry: &LogEntry) {
+        if !entry.has_timestamp() {
+            return;
+        }
+        // buckets are aligned and displayed using the time zone of the first entry
+        let offset = i64::from(
+            self.offset
+                .get_or_insert(*entry.timestamp.offset())
+                .local_minus_utc(),
+        );
+        let local = entry.timestamp.timestamp() + offset;
+        let start = local.div_euclid(self.bucket) * self.bucket - offset;
+        let classes = self.buckets.entry(start).or_default();
+        classes[status_class_index(entry.status)] += 1;
+    }
+
+    /// All buckets from the first to the last one including empty buckets. Buckets are enlarged
+    /// to `bucket()` size when log period is too long to fit `MAX_BUCKETS` requested ones
+    #[must_use]
+    pub fn buckets(&self) -> Vec<Bucket> {
+        let Some(offset) = self.offset else {
+            return vec![];
+        };
+        let size = self.size();
+        let local_offset = i64::from(offset.local_minus_utc());
+        let mut buckets: BTreeMap<i64, [u64; STATUS_CLASSES.len()]> = BTreeMap::new();
+        for (start, classes) in &self.buckets {
+            let start = (start + local_offset).div_euclid(size) * size - local_offset;
+            let merged = buckets.entry(start).or_default();
+            for (m, c) in merged.iter_mut().zip(classes) {
+                *m += c;
+            }
+        }
+        let (Some((first, _)), Some((last, _))) =
+            (buckets.first_key_value(), buckets.last_key_value())
+        else {
+            return vec![];
+        };
+        (*first..=*last)
+            .step_by(usize::try_from(size).unwrap_or(usize::MAX))
+            .filter_map(|start| {
+                let time = DateTime::from_timestamp(start, 0)?.with_timezone(&offset);
+                Some(Bucket {
+                    start: time,
+                    classes: buckets.get(&start).copied().unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    /// Size of displayed buckets. It's a multiple of requested size if log period is too long
+    #[must_use]
+    pub fn bucket(&self) -> TimeDelta {
+        TimeDelta::seconds(self.size())
+    }
+
+    /// Whether requested bucket size was enlarged to limit the number of buckets
+    #[must_use]
+    pub fn is_enlarged(&self) -> bool {
+        self.size() != self.bucket
+    }
+
+    fn size(&self) -> i64 {
+        let (Some((first, _)), Some((last, _))) = (
+            self.buckets.first_key_value(),
+            self.buckets.last_key_value(),
+        ) else {
+            return self.bucket;
+        };
+        let count = (last - first) / self.bucket + 1;
+        // enlarged buckets are realigned so one more of them may be needed
+        let limit = MAX_BUCKETS - 1;
+        let factor = (count + limit - 1) / limit;
+        self.bucket * factor.max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(timestamp: &str, status: u16) -> LogEntry {
+        LogEntryBuilder::new()
+            .timestamp(timestamp)
+            .status(status)
+            .build()
+    }
+
+    #[test]
+    fn timeline_buckets_with_gaps() {
+        // Arrange
+        let mut timeline = Timeline::new(TimeDelta::hours(1));
+
+        // Act
+        timeline.add(&entry("2024-05-01T10:15:00+03:00", 200));
+        timeline.add(&entry("2024-05-01T10:45:00+03:00", 404));
+        timeline.add(&entry("2024-05-01T12:01:00+03:00", 500));
+        timeline.add(&LogEntry::default());
+
+        // Assert
+        let buckets = timeline.buckets();
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(
+            buckets[0].start,
+            DateTime::parse_from_rfc3339("2024-05-01T10:00:00+03:00").unwrap()
+        );
+        assert_eq!(buckets[0].classes, [0, 1, 0, 1, 0, 0]);
+        assert_eq!(buckets[1].total(), 0);
+        assert_eq!(buckets[2].classes, [0, 0, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn timeline_daily_buckets_aligned_to_local_midnight() {
+        // Arrange
+        let mut timeline = Timeline::new(TimeDelta::days(1));
+
+        // Act
+        timeline.add(&entry("2024-05-01T01:15:00+03:00", 200));
+
+        // Assert
+        let buckets = timeline.buckets();
+        assert_eq!(
+            buckets[0].start,
+            DateTime::parse_from_rfc3339("2024-05-01T00:00:00+03:00").unwrap()
+        );
+    }
+
+    #[test]
+    fn timeline_empty() {
+        // Arrange
+        let timeline = Timeline::new(TimeDelta::hours(1));
+
+        // Act
+        let buckets = timeline.buckets();
+
+        // Assert
+        assert!(buckets.is_empty());
+    }
+
+    #[test]
+    fn long_period_buckets_enlarged() {
+        // Arrange
+        let mut timeline = Timeline::new(TimeDelta::seconds(1));
+
+        // Act
+        timeline.add(&entry("2024-05-01T00:00:00Z", 200));
+        timeline.add(&entry("2024-05-01T00:00:30Z", 200));
+        timeline.add(&entry("2024-05-31T23:59:59Z", 500));
+
+        // Assert
+        let buckets = timeline.buckets();
+        assert!(timeline.is_enlarged());
+        assert!(buckets.len() <= MAX_BUCKETS as usize, "{}", buckets.len());
+        assert_eq!(timeline.bucket(), TimeDelta::seconds(2682));
+        assert_eq!(buckets.iter().map(Bucket::total).sum::<u64>(), 3);
+    }
+}
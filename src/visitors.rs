@@ -0,0 +1,324 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::LazyLock;
+
+use chrono::{Datelike, NaiveDate, TimeDelta};
+use clap::{ValueEnum, builder::PossibleValue};
+use regex::Regex;
+
+use crate::LogEntry;
+use crate::sketch::{HyperLogLog, hash_of};
+
+/// User agents of crawlers, monitoring tools, HTTP libraries and link previews. Bots are matched
+/// by versioned `...bot/1.0` tokens or contact URLs and known names because plain `bot`, `monitor`
+/// or `preview` substrings occur in browser agents too (e.g. `CUBOT` phones)
+static BOT_AGENTS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\bbot\b|bot/\d|\+https?://|crawl|spider|slurp|archiver|fetcher|scanner|uptime|pingdom|statuscake|\bmonitoring\b|telegrambot|twitterbot|slackbot|whatsapp/|bingpreview|skypeuripreview|google web preview|facebookexternalhit|headless|curl|wget|python|go-http-client|java/|okhttp|libwww|httpclient|axios|node-fetch|^-?$",
+    )
+    .expect("bot agents pattern is valid")
+});
+
+/// Whether user agent belongs to bot rather than to browser
+#[must_use]
+pub fn is_bot(agent: &str) -> bool {
+    BOT_AGENTS.is_match(agent.trim_matches('"'))
+}
+
+/// Period visitors are counted within
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitorsPeriod {
+    Day,
+    /// ISO week started from Monday
+    Week,
+}
+
+impl VisitorsPeriod {
+    fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            VisitorsPeriod::Day => date,
+            VisitorsPeriod::Week => {
+                date - TimeDelta::days(i64::from(date.weekday().num_days_from_monday()))
+            }
+        }
+    }
+}
+
+impl ValueEnum for VisitorsPeriod {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[VisitorsPeriod::Day, VisitorsPeriod::Week]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            VisitorsPeriod::Day => PossibleValue::new("day"),
+            VisitorsPeriod::Week => PossibleValue::new("week"),
+        })
+    }
+}
+
+/// Unique visitors of a period
+#[derive(Debug, PartialEq, Eq)]
+pub struct PeriodVisitors {
+    pub start: NaiveDate,
+    pub visitors: u64,
+    /// Visitors seen for the first time in this period
+    pub new: u64,
+    /// Visitors seen in earlier periods too. Estimated with about 1.6% error of period visitors
+    pub returning: u64,
+    pub requests: u64,
+}
+
+/// Unique visitors per period with totals
+#[derive(Debug, Default)]
+pub struct VisitorsSummary {
+    /// All periods from the first to the last one including periods without visitors
+    pub periods: Vec<PeriodVisitors>,
+    pub visitors: u64,
+    pub requests: u64,
+    /// Requests of bots excluded from counting
+    pub bot_requests: u64,
+}
+
+/// Counts unique visitors identified by client IP and user agent. The first period of every
+/// visitor is tracked exactly by hash of client and agent (about 16 bytes per visitor) so new
+/// visitors and the total are exact. Returning visitors are estimated by `HyperLogLog` of period
+#[derive(Debug)]
+pub struct VisitorsCounter {
+    period: VisitorsPeriod,
+    exclude_bots: bool,
+    bot_requests: u64,
+    periods: BTreeMap<NaiveDate, (HyperLogLog, u64)>,
+    first_seen: HashMap<u64, NaiveDate>,
+}
+
+impl VisitorsCounter {
+    #[must_use]
+    pub fn new(period: VisitorsPeriod, exclude_bots: bool) -> Self {
+        Self {
+            period,
+            exclude_bots,
+            bot_requests: 0,
+            periods: BTreeMap::new(),
+            first_seen: HashMap::new(),
+        }
+    }
+
+    /// Adds entry into its period. Entries without timestamp are ignored
+    pub fn add(&mut self, entry: &LogEntry) {
+        if !entry.has_timestamp() {
+            return;
+        }
+        if self.exclude_bots && is_bot(&entry.agent) {
+            self.bot_requests += 1;
+            return;
+        }
+        let start = self.period.start(entry.timestamp.date_naive());
+        let hash = hash_of(&(&entry.clientip, &entry.agent));
+        let (visitors, requests) = self.periods.entry(start).or_default();
+        visitors.insert_hash(hash);
+        *requests += 1;
+        // log isn't necessarily ordered so the earliest period wins
+        self.first_seen
+            .entry(hash)
+            .and_modify(|first| *first = (*first).min(start))
+            .or_insert(start);
+    }
+
+    #[must_use]
+    pub fn finish(self) -> VisitorsSummary {
+        let mut summary = VisitorsSummary {
+            bot_requests: self.bot_requests,
+            ..Default::default()
+        };
+        let (Some(first), Some(last)) = (
+            self.periods.first_key_value().map(|(d, _)| *d),
+            self.periods.last_key_value().map(|(d, _)| *d),
+        ) else {
+            return summary;
+        };
+        let step = match self.period {
+            VisitorsPeriod::Day => TimeDelta::days(1),
+            VisitorsPeriod::Week => TimeDelta::weeks(1),
+        };
+        let mut new_visitors: HashMap<NaiveDate, u64> = HashMap::new();
+        for first in self.first_seen.values() {
+            *new_visitors.entry(*first).or_default() += 1;
+        }
+        let mut earlier = 0;
+        let mut start = first;
+        while start <= last {
+            let new = new_visitors.get(&start).copied().unwrap_or_default();
+            let (returning, requests) =
+                self.periods
+                    .get(&start)
+                    .map_or((0, 0), |(period, requests)| {
+                        // only visitors of earlier periods may return so the estimate error is capped
+                        let returning = period.estimate().saturating_sub(new).min(earlier);
+                        (returning, *requests)
+                    });
+            summary.requests += requests;
+            summary.periods.push(PeriodVisitors {
+                start,
+                visitors: new + returning,
+                new,
+                returning,
+                requests,
+            });
+            earlier += new;
+            start += step;
+        }
+        summary.visitors = self.first_seen.len() as u64;
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::LogEntryBuilder;
+
+    fn entry(client: &str, agent: &str, timestamp: &str) -> LogEntry {
+        LogEntryBuilder::new()
+            .client(client)
+            .agent(agent)
+            .timestamp(timestamp)
+            .build()
+    }
+
+    #[test_case(
+        "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+        true
+    )]
+    #[test_case("TelegramBot (like TwitterBot)", true)]
+    #[test_case("Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)", true)]
+    #[test_case(
+        "Mozilla/5.0+(compatible; UptimeRobot/2.0; http://www.uptimerobot.com/)",
+        true
+    )]
+    #[test_case("curl/8.0", true)]
+    #[test_case("python-requests/2.31", true)]
+    #[test_case("-", true)]
+    #[test_case(
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) Chrome/124.0 Safari/537.36",
+        false
+    )]
+    #[test_case(
+        "Mozilla/5.0 (Linux; Android 10; CUBOT X30) AppleWebKit/537.36 Chrome/120.0 Mobile Safari/537.36",
+        false
+    )]
+    #[test_case(
+        "Mozilla/5.0 (Linux; Android 11; Cubot_Note 20) AppleWebKit/537.36 Chrome/118.0 Mobile Safari/537.36",
+        false
+    )]
+    fn is_bot_tests(agent: &str, expected: bool) {
+        // Arrange
+
+        // Act
+        let actual = is_bot(agent);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn daily_visitors_new_and_returning() {
+        // Arrange
+        let mut counter = VisitorsCounter::new(VisitorsPeriod::Day, false);
+
+        // Act
+        counter.add(&entry("10.0.0.1", "Firefox", "2024-05-03T10:00:00Z"));
+        counter.add(&entry("10.0.0.1", "Firefox", "2024-05-01T10:00:00Z"));
+        counter.add(&entry("10.0.0.1", "Firefox", "2024-05-01T11:00:00Z"));
+        counter.add(&entry("10.0.0.1", "Chrome", "2024-05-01T12:00:00Z"));
+        counter.add(&entry("10.0.0.2", "Firefox", "2024-05-03T12:00:00Z"));
+        counter.add(&LogEntry::default());
+        let actual = counter.finish();
+
+        // Assert
+        assert_eq!(actual.visitors, 3);
+        assert_eq!(actual.requests, 5);
+        let day = |d| NaiveDate::from_ymd_opt(2024, 5, d).unwrap();
+        assert_eq!(
+            actual.periods,
+            vec![
+                PeriodVisitors {
+                    start: day(1),
+                    visitors: 2,
+                    new: 2,
+                    returning: 0,
+                    requests: 3,
+                },
+                PeriodVisitors {
+                    start: day(2),
+                    visitors: 0,
+                    new: 0,
+                    returning: 0,
+                    requests: 0,
+                },
+                PeriodVisitors {
+                    start: day(3),
+                    visitors: 2,
+                    new: 1,
+                    returning: 1,
+                    requests: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn weekly_visitors_without_bots() {
+        // Arrange
+        let mut counter = VisitorsCounter::new(VisitorsPeriod::Week, true);
+
+        // Act
+        counter.add(&entry("10.0.0.1", "Firefox", "2024-05-06T10:00:00Z"));
+        counter.add(&entry("10.0.0.1", "Firefox", "2024-05-12T10:00:00Z"));
+        counter.add(&entry("10.0.0.2", "Googlebot/2.1", "2024-05-07T10:00:00Z"));
+        let actual = counter.finish();
+
+        // Assert
+        assert_eq!(actual.bot_requests, 1);
+        assert_eq!(actual.periods.len(), 1);
+        assert_eq!(
+            actual.periods[0].start,
+            NaiveDate::from_ymd_opt(2024, 5, 6).unwrap()
+        );
+        assert_eq!(actual.periods[0].visitors, 1);
+        assert_eq!(actual.periods[0].requests, 2);
+    }
+
+    #[test]
+    fn new_visitors_exact_beyond_sparse_sketch() {
+        // Arrange
+        let mut counter = VisitorsCounter::new(VisitorsPeriod::Day, false);
+
+        // Act
+        // day 1: 2000 visitors, day 2: the same 2000 and 1000 new ones, day 3: 1500 new ones
+        for i in 0..2000 {
+            let client = format!("10.0.{}.{}", i / 256, i % 256);
+            counter.add(&entry(&client, "Firefox", "2024-05-01T10:00:00Z"));
+            counter.add(&entry(&client, "Firefox", "2024-05-02T10:00:00Z"));
+        }
+        for i in 0..1000 {
+            let client = format!("10.1.{}.{}", i / 256, i % 256);
+            counter.add(&entry(&client, "Firefox", "2024-05-02T12:00:00Z"));
+        }
+        for i in 0..1500 {
+            let client = format!("10.2.{}.{}", i / 256, i % 256);
+            counter.add(&entry(&client, "Firefox", "2024-05-03T12:00:00Z"));
+        }
+        let actual = counter.finish();
+
+        // Assert
+        assert_eq!(actual.visitors, 4500);
+        let new: Vec<u64> = actual.periods.iter().map(|p| p.new).collect();
+        assert_eq!(new, vec![2000, 1000, 1500]);
+        let returning: Vec<u64> = actual.periods.iter().map(|p| p.returning).collect();
+        assert_eq!(returning[0], 0);
+        assert!(returning[1].abs_diff(2000) < 100, "{returning:?}");
+        assert!(returning[2] < 100, "{returning:?}");
+    }
+}